@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
+
+const ENGLISH: &str = "one 1
+two 2
+three 3
+four 4
+five 5
+six 6
+seven 7
+eight 8
+nine 9";
+
+/// Tokens standing for digits: the digits themselves, and spelled out words.
+#[derive(Debug, Clone)]
+pub struct Vocabulary(Vec<(String, u32)>);
+impl Vocabulary {
+    /// Only the digit characters
+    pub fn digits() -> Self {
+        Self(
+            (0..10)
+                .map(|d| (char::from_digit(d, 10).unwrap().to_string(), d))
+                .collect(),
+        )
+    }
+
+    /// Digit characters and the English words from one to nine
+    pub fn english() -> Self {
+        Self::from_str(ENGLISH).unwrap()
+    }
+
+    /// First token of `line`, scanning from the left
+    pub fn first(&self, line: &str) -> Option<u32> {
+        line.char_indices().find_map(|(i, _)| {
+            self.0
+                .iter()
+                .find(|(token, _)| line[i..].starts_with(token.as_str()))
+                .map(|&(_, d)| d)
+        })
+    }
+
+    /// Last token of `line`, scanning from the right. Tokens may overlap the ones found by `first`, so the last
+    /// digit of `twone` is 1.
+    pub fn last(&self, line: &str) -> Option<u32> {
+        line.char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .rev()
+            .find_map(|end| {
+                self.0
+                    .iter()
+                    .find(|(token, _)| line[..end].ends_with(token.as_str()))
+                    .map(|&(_, d)| d)
+            })
+    }
+
+    /// Number made of the first and last digits of `line`
+    pub fn calibration_value(&self, line: &str) -> Result<u32, Error> {
+        match (self.first(line), self.last(line)) {
+            (Some(first), Some(last)) => Ok(first * 10 + last),
+            _ => Err(anyhow!("no digit in '{line}'")),
+        }
+    }
+
+    pub fn calibration_sum(&self, data: &str) -> Result<u32, Error> {
+        data.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                self.calibration_value(line)
+                    .map_err(|e| anyhow!("line {}: {e}", i + 1))
+            })
+            .sum()
+    }
+}
+/// The digit characters, extended with words given one per line with their digit, such as `eins 1`
+impl FromStr for Vocabulary {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut vocabulary = Self::digits();
+        for (i, line) in s.lines().enumerate() {
+            let err = |msg: String| anyhow!("line {}: {msg}", i + 1);
+            let mut tokens = line.split_whitespace();
+            let Some(word) = tokens.next() else {
+                continue;
+            };
+            let digit = tokens
+                .next()
+                .and_then(|d| d.parse().ok())
+                .filter(|&d: &u32| d < 10)
+                .ok_or_else(|| err(format!("expected a digit after '{word}'")))?;
+            vocabulary.0.push((word.to_string(), digit));
+        }
+        Ok(vocabulary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let data = "1abc2
+pqr3stu8vwx
+a1b2c3d4e5f
+treb7uchet";
+        assert_eq!(Vocabulary::digits().calibration_sum(data).unwrap(), 142);
+
+        let data = "two1nine
+eightwothree
+abcone2threexyz
+xtwone3four
+4nineeightseven2
+zoneight234
+7pqrstsixteen";
+        assert_eq!(Vocabulary::english().calibration_sum(data).unwrap(), 281);
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let english = Vocabulary::english();
+        assert_eq!(english.calibration_value("twone").unwrap(), 21);
+        assert_eq!(english.calibration_value("oneight").unwrap(), 18);
+        assert_eq!(english.calibration_value("sevenine").unwrap(), 79);
+    }
+
+    #[test]
+    fn test_other_language() {
+        let german = Vocabulary::from_str("eins 1\nzwei 2\ndrei 3\nfünf 5\nneun 9").unwrap();
+        assert_eq!(german.calibration_value("xfünfzweiy").unwrap(), 52);
+        assert_eq!(german.calibration_value("neuneins").unwrap(), 91);
+        let err = Vocabulary::digits().calibration_sum("1\nabc").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: no digit in 'abc'");
+        let err = Vocabulary::from_str("zehn 10").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: expected a digit after 'zehn'");
+    }
+}
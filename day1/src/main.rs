@@ -1,46 +1,33 @@
-fn main() {
-    let puzzle_input = util::fetch_puzzle_input(1).unwrap();
-    println!("part1={}", part1(&puzzle_input));
-    println!("part2={}", part2(&puzzle_input));
+use std::str::FromStr;
+
+use common::Vocabulary;
+use util::clap::{self, Args};
+
+mod common;
+
+#[derive(Args)]
+struct Options {
+    /// File of spelled out digits for part 2, one `word digit` per line, replacing the English words
+    #[arg(long)]
+    vocabulary: Option<String>,
 }
 
-fn part1(data: &str) -> String {
-    let ans: u32 = data
-        .lines()
-        .map(|line| {
-            let digits: Vec<u32> = line.chars().filter_map(|c| c.to_digit(10)).collect();
-            let twodigit = digits.last().unwrap() + digits.first().unwrap() * 10;
-            twodigit
-        })
-        .sum();
+fn main() {
+    util::run!(Options);
+}
 
+fn part1(file: &str, _: &Options) -> String {
+    let data = std::fs::read_to_string(file).unwrap();
+    let ans = Vocabulary::digits().calibration_sum(&data).unwrap();
     ans.to_string()
 }
 
-fn part2(data: &str) -> String {
-    let replace_with_digit = |line: &str| {
-        line.replace("one", "o1e")
-            .replace("two", "t2o")
-            .replace("three", "t3e")
-            .replace("four", "4")
-            .replace("five", "5e")
-            .replace("six", "6")
-            .replace("seven", "7n")
-            .replace("eight", "e8t")
-            .replace("nine", "n9e")
+fn part2(file: &str, options: &Options) -> String {
+    let data = std::fs::read_to_string(file).unwrap();
+    let vocabulary = match &options.vocabulary {
+        Some(file) => Vocabulary::from_str(&std::fs::read_to_string(file).unwrap()).unwrap(),
+        None => Vocabulary::english(),
     };
-
-    let ans: u32 = data
-        .lines()
-        .map(|line| {
-            let digits: Vec<u32> = replace_with_digit(line)
-                .chars()
-                .filter_map(|c| c.to_digit(10))
-                .collect();
-            let twodigit = digits.last().unwrap() + digits.first().unwrap() * 10;
-            twodigit
-        })
-        .sum();
-
+    let ans = vocabulary.calibration_sum(&data).unwrap();
     ans.to_string()
 }
@@ -1,10 +1,10 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashSet, fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Error};
 use util::grid::Grid;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-enum TileType {
+pub enum TileType {
     Vertical,
     Horizontal,
     NorthEast,
@@ -30,6 +30,43 @@ impl TryFrom<char> for TileType {
         }
     }
 }
+impl TileType {
+    const PIPES: [TileType; 6] = [
+        Self::Vertical,
+        Self::Horizontal,
+        Self::NorthEast,
+        Self::NorthWest,
+        Self::SouthWest,
+        Self::SouthEast,
+    ];
+
+    /// Directions in which the pipe connects to its neighbours. The starting tile has no connection of its
+    /// own, it takes the shape of the pipe it hides.
+    fn connections(&self) -> &'static [Direction] {
+        match self {
+            Self::Vertical => &[Direction::North, Direction::South],
+            Self::Horizontal => &[Direction::East, Direction::West],
+            Self::NorthEast => &[Direction::North, Direction::East],
+            Self::NorthWest => &[Direction::North, Direction::West],
+            Self::SouthWest => &[Direction::South, Direction::West],
+            Self::SouthEast => &[Direction::South, Direction::East],
+            Self::Ground | Self::Starting => &[],
+        }
+    }
+
+    fn box_drawing(&self) -> char {
+        match self {
+            Self::Vertical => '│',
+            Self::Horizontal => '─',
+            Self::NorthEast => '└',
+            Self::NorthWest => '┘',
+            Self::SouthWest => '┐',
+            Self::SouthEast => '┌',
+            Self::Ground => '.',
+            Self::Starting => 'S',
+        }
+    }
+}
 impl Display for TileType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let c = match self {
@@ -46,17 +83,42 @@ impl Display for TileType {
     }
 }
 
-#[derive(PartialEq, Debug)]
-enum VerticalDirection {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
     North,
-    South,
-    Undef,
-}
-#[derive(Debug, PartialEq)]
-enum HorizontalDirection {
     East,
+    South,
     West,
-    Undef,
+}
+impl Direction {
+    const ALL: [Direction; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    fn opposite(&self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+}
+
+/// A closed loop of pipes
+#[derive(Debug)]
+pub struct PipeLoop {
+    /// Tiles of the loop, in walking order
+    pub tiles: Vec<(usize, usize)>,
+}
+impl PipeLoop {
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Tile of the loop the farthest from its first tile, with its distance in steps
+    pub fn farthest(&self) -> ((usize, usize), usize) {
+        let distance = self.len() / 2;
+        (self.tiles[distance], distance)
+    }
 }
 
 #[derive(Debug)]
@@ -80,142 +142,296 @@ impl Diagram {
         })
     }
 
-    fn initial_flow(&self) -> ((usize, usize), HorizontalDirection, VerticalDirection) {
-        let tile_loc = self.0.find(&TileType::Starting).unwrap();
-
-        for (x, y, hor, vert) in [
-            (
-                Some(tile_loc.0 + 1),
-                Some(tile_loc.1),
-                HorizontalDirection::East,
-                VerticalDirection::Undef,
-            ),
-            (
-                Some(tile_loc.0),
-                Some(tile_loc.1 + 1),
-                HorizontalDirection::Undef,
-                VerticalDirection::South,
-            ),
-            (
-                tile_loc.0.checked_sub(1),
-                Some(tile_loc.1),
-                HorizontalDirection::West,
-                VerticalDirection::Undef,
-            ),
-            (
-                Some(tile_loc.0),
-                tile_loc.1.checked_sub(1),
-                HorizontalDirection::Undef,
-                VerticalDirection::North,
-            ),
-        ] {
-            if let (Some(x), Some(y)) = (x, y) {
-                let tile = self.0.get((x, y)).unwrap();
-                if hor == HorizontalDirection::East {
-                    match tile {
-                        TileType::NorthWest | TileType::SouthWest | TileType::Horizontal => {
-                            return ((x, y), hor, vert);
-                        }
-                        _ => continue,
-                    }
-                } else if hor == HorizontalDirection::West {
-                    match tile {
-                        TileType::NorthEast | TileType::SouthEast | TileType::Horizontal => {
-                            return ((x, y), hor, vert);
-                        }
-                        _ => continue,
-                    }
-                } else if vert == VerticalDirection::North {
-                    match tile {
-                        TileType::SouthEast | TileType::SouthWest | TileType::Vertical => {
-                            return ((x, y), hor, vert);
-                        }
-                        _ => continue,
-                    }
-                } else if vert == VerticalDirection::South {
-                    match tile {
-                        TileType::NorthEast | TileType::NorthWest | TileType::Vertical => {
-                            return ((x, y), hor, vert);
-                        }
-                        _ => continue,
-                    }
-                }
-            }
+    fn neighbour(&self, (x, y): (usize, usize), direction: Direction) -> Option<(usize, usize)> {
+        let pos = match direction {
+            Direction::North => (x, y.checked_sub(1)?),
+            Direction::East => (x + 1, y),
+            Direction::South => (x, y + 1),
+            Direction::West => (x.checked_sub(1)?, y),
+        };
+        self.0.get(pos).map(|_| pos)
+    }
+
+    /// Directions in which the neighbours of `pos` have a pipe connecting back to it
+    fn connected_neighbours(&self, pos: (usize, usize)) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| {
+                self.neighbour(pos, direction)
+                    .and_then(|n| self.0.get(n))
+                    .is_some_and(|tile| tile.connections().contains(&direction.opposite()))
+            })
+            .collect()
+    }
+
+    /// The pipe hidden under the starting tile: the pair of connecting neighbours which closes a loop through it.
+    /// Other pipes may point at the starting tile without being part of the loop.
+    pub fn starting_tile_type(&self) -> Result<TileType, Error> {
+        let start = self
+            .0
+            .find(&TileType::Starting)
+            .ok_or(anyhow!("no starting tile"))?;
+        let connections = self.connected_neighbours(start);
+        if connections.len() < 2 {
+            return Err(anyhow!(
+                "the starting tile at {start:?} is connected to {} pipe(s)",
+                connections.len()
+            ));
         }
-        panic!()
+        TileType::PIPES
+            .into_iter()
+            .filter(|pipe| pipe.connections().iter().all(|d| connections.contains(d)))
+            .find(|&pipe| self.walk(start, pipe).is_ok())
+            .ok_or(anyhow!(
+                "none of the {} pipes connected to the starting tile at {start:?} close a loop",
+                connections.len()
+            ))
     }
-    pub fn run(&self) -> Vec<(usize, usize)> {
-        let (mut tile_loc, mut horizontal, mut vertical) = self.initial_flow();
 
-        let mut visit = vec![];
+    /// Tile at `pos`, with the starting tile replaced by `start_pipe`
+    fn pipe_at(&self, pos: (usize, usize), start_pipe: TileType) -> Result<TileType, Error> {
+        match self.0.get(pos) {
+            Some(TileType::Starting) => Ok(start_pipe),
+            Some(&tile) => Ok(tile),
+            None => Err(anyhow!("{pos:?} is outside of the diagram")),
+        }
+    }
 
+    /// Follow the pipes from `start` until coming back to it, the starting tile hiding `start_pipe`
+    fn walk(&self, start: (usize, usize), start_pipe: TileType) -> Result<PipeLoop, Error> {
+        let mut tiles = vec![];
+        self.follow(start, start_pipe, &mut tiles)?;
+        Ok(PipeLoop { tiles })
+    }
+
+    /// Push the tiles met while following the pipes from `start` into `tiles`, until coming back to `start` or
+    /// failing. The tiles of a failed walk form an open chain, none of them is part of a loop.
+    fn follow(
+        &self,
+        start: (usize, usize),
+        start_pipe: TileType,
+        tiles: &mut Vec<(usize, usize)>,
+    ) -> Result<(), Error> {
+        let mut pos = start;
+        let mut came_from = None;
         loop {
-            visit.push(tile_loc);
-            let tile = self.0.get(tile_loc).unwrap();
-            match tile {
-                TileType::Horizontal => {
-                    if horizontal == HorizontalDirection::East {
-                        tile_loc = (tile_loc.0 + 1, tile_loc.1)
-                    } else if horizontal == HorizontalDirection::West {
-                        tile_loc = (tile_loc.0 - 1, tile_loc.1)
-                    }
-                }
-                TileType::NorthEast => {
-                    if horizontal == HorizontalDirection::West {
-                        tile_loc = (tile_loc.0, tile_loc.1 - 1);
-                        horizontal = HorizontalDirection::Undef;
-                        vertical = VerticalDirection::North;
-                    } else if vertical == VerticalDirection::South {
-                        tile_loc = (tile_loc.0 + 1, tile_loc.1);
-                        horizontal = HorizontalDirection::East;
-                        vertical = VerticalDirection::Undef;
-                    }
-                }
-                TileType::NorthWest => {
-                    if horizontal == HorizontalDirection::East {
-                        tile_loc = (tile_loc.0, tile_loc.1 - 1);
-                        horizontal = HorizontalDirection::Undef;
-                        vertical = VerticalDirection::North;
-                    } else if vertical == VerticalDirection::South {
-                        tile_loc = (tile_loc.0 - 1, tile_loc.1);
-                        horizontal = HorizontalDirection::West;
-                        vertical = VerticalDirection::Undef;
-                    }
+            tiles.push(pos);
+            let pipe = self.pipe_at(pos, start_pipe)?;
+            let &direction = pipe
+                .connections()
+                .iter()
+                .find(|&&d| Some(d) != came_from)
+                .ok_or(anyhow!("{pos:?} is not a pipe"))?;
+            let next = self
+                .neighbour(pos, direction)
+                .ok_or(anyhow!("the pipe at {pos:?} leads out of the diagram"))?;
+            if !self
+                .pipe_at(next, start_pipe)?
+                .connections()
+                .contains(&direction.opposite())
+            {
+                return Err(anyhow!(
+                    "the pipe at {pos:?} leads to {next:?}, which doesn't connect back"
+                ));
+            }
+            if next == start {
+                return Ok(());
+            }
+            pos = next;
+            came_from = Some(direction.opposite());
+        }
+    }
+
+    /// Every closed loop of pipes of the diagram, with why each chain of pipes which is not a loop stops
+    pub fn loops(&self) -> (Vec<PipeLoop>, Vec<Error>) {
+        // Without a loop through it, the starting tile connects to nothing
+        let start_pipe = self.starting_tile_type().unwrap_or(TileType::Ground);
+        let mut seen = HashSet::new();
+        let mut loops = vec![];
+        let mut errors = vec![];
+        for (y, row) in self.0.rows().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                if *tile == TileType::Ground || seen.contains(&(x, y)) {
+                    continue;
                 }
-                TileType::SouthEast => {
-                    if horizontal == HorizontalDirection::West {
-                        tile_loc = (tile_loc.0, tile_loc.1 + 1);
-                        horizontal = HorizontalDirection::Undef;
-                        vertical = VerticalDirection::South;
-                    } else if vertical == VerticalDirection::North {
-                        tile_loc = (tile_loc.0 + 1, tile_loc.1);
-                        horizontal = HorizontalDirection::East;
-                        vertical = VerticalDirection::Undef;
-                    }
+                let mut tiles = vec![];
+                let followed = self.follow((x, y), start_pipe, &mut tiles);
+                seen.extend(tiles.iter().copied());
+                match followed {
+                    Ok(()) => loops.push(PipeLoop { tiles }),
+                    Err(e) => errors.push(e),
                 }
-                TileType::SouthWest => {
-                    if horizontal == HorizontalDirection::East {
-                        tile_loc = (tile_loc.0, tile_loc.1 + 1);
-                        horizontal = HorizontalDirection::Undef;
-                        vertical = VerticalDirection::South;
-                    } else if vertical == VerticalDirection::North {
-                        tile_loc = (tile_loc.0 - 1, tile_loc.1);
-                        horizontal = HorizontalDirection::West;
-                        vertical = VerticalDirection::Undef;
+            }
+        }
+        (loops, errors)
+    }
+
+    /// Tiles strictly inside the loop, found by counting the loop crossings on the left of each tile
+    pub fn enclosed(&self) -> Result<HashSet<(usize, usize)>, Error> {
+        let (starting_tile_type, main_loop) = self.main_loop()?;
+        Ok(self.enclosed_by(&main_loop, starting_tile_type))
+    }
+
+    /// Tiles strictly inside `main_loop`, the starting tile hiding `starting_tile_type`
+    fn enclosed_by(
+        &self,
+        main_loop: &HashSet<(usize, usize)>,
+        starting_tile_type: TileType,
+    ) -> HashSet<(usize, usize)> {
+        let mut enclosed = HashSet::new();
+        for (y, row) in self.0.rows().enumerate() {
+            let mut inside = false;
+            for (x, &tile) in row.iter().enumerate() {
+                if main_loop.contains(&(x, y)) {
+                    let tile = if tile == TileType::Starting {
+                        starting_tile_type
+                    } else {
+                        tile
+                    };
+                    // Crossing the loop through its tiles with a northward connection only, so that `L-7`
+                    // counts as one crossing and `L-J` as none
+                    if matches!(
+                        tile,
+                        TileType::Vertical | TileType::NorthEast | TileType::NorthWest
+                    ) {
+                        inside = !inside;
                     }
+                } else if inside {
+                    enclosed.insert((x, y));
                 }
-                TileType::Vertical => {
-                    if vertical == VerticalDirection::North {
-                        tile_loc = (tile_loc.0, tile_loc.1 - 1);
-                    } else if vertical == VerticalDirection::South {
-                        tile_loc = (tile_loc.0, tile_loc.1 + 1);
+            }
+        }
+        enclosed
+    }
+
+    /// Draw the loop with box-drawing characters, the tiles it encloses as `I` and everything else as `.`
+    pub fn render(&self) -> Result<String, Error> {
+        let (starting_tile_type, main_loop) = self.main_loop()?;
+        let enclosed = self.enclosed_by(&main_loop, starting_tile_type);
+
+        let mut s = String::new();
+        for (y, row) in self.0.rows().enumerate() {
+            for (x, &tile) in row.iter().enumerate() {
+                let c = if main_loop.contains(&(x, y)) {
+                    match tile {
+                        TileType::Starting => starting_tile_type.box_drawing(),
+                        tile => tile.box_drawing(),
                     }
-                }
-                TileType::Starting => {
-                    return visit;
-                }
-                _ => (),
+                } else if enclosed.contains(&(x, y)) {
+                    'I'
+                } else {
+                    '.'
+                };
+                s.push(c);
             }
+            s.push('\n');
         }
+        Ok(s)
+    }
+
+    /// Tiles of the loop going through the starting tile
+    pub fn run(&self) -> Result<Vec<(usize, usize)>, Error> {
+        self.loop_through_start(self.starting_tile_type()?)
+    }
+
+    /// Pipe hidden under the starting tile, with the set of tiles of the loop going through it
+    fn main_loop(&self) -> Result<(TileType, HashSet<(usize, usize)>), Error> {
+        let starting_tile_type = self.starting_tile_type()?;
+        let tiles = self.loop_through_start(starting_tile_type)?;
+        Ok((starting_tile_type, tiles.into_iter().collect()))
+    }
+
+    fn loop_through_start(
+        &self,
+        starting_tile_type: TileType,
+    ) -> Result<Vec<(usize, usize)>, Error> {
+        let start = self
+            .0
+            .find(&TileType::Starting)
+            .ok_or(anyhow!("no starting tile"))?;
+        Ok(self.walk(start, starting_tile_type)?.tiles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+...........";
+
+    #[test]
+    fn test_enclosed() {
+        let diagram = Diagram::from_str(EXAMPLE).unwrap();
+        assert_eq!(diagram.starting_tile_type().unwrap(), TileType::SouthEast);
+        assert_eq!(diagram.enclosed().unwrap().len(), 4);
+        assert_eq!(
+            diagram.render().unwrap(),
+            "...........
+.┌───────┐.
+.│┌─────┐│.
+.││.....││.
+.││.....││.
+.│└─┐.┌─┘│.
+.│II│.│II│.
+.└──┘.└──┘.
+...........
+"
+        );
+    }
+
+    #[test]
+    fn test_loops() {
+        let s = "-L|F7\n7S-7|\nL|7||\n-L-J|\nL|-JF\n.....\n.F7..\n.LJ..";
+        let diagram = Diagram::from_str(s).unwrap();
+        assert_eq!(diagram.run().unwrap().len(), 8);
+        let (loops, errors) = diagram.loops();
+        assert_eq!(loops.len(), 2);
+        assert_eq!(errors.len(), 12);
+        assert_eq!(
+            errors[1].to_string(),
+            "the pipe at (1, 0) leads out of the diagram"
+        );
+        assert_eq!(loops[0].len(), 8);
+        assert_eq!(loops[0].farthest(), ((3, 3), 4));
+        assert_eq!(loops[1].tiles, vec![(1, 6), (1, 7), (2, 7), (2, 6)]);
+    }
+
+    #[test]
+    fn test_branching_start() {
+        let diagram = Diagram::from_str(".|.\n-S-\n.|.").unwrap();
+        let err = diagram.run().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "none of the 4 pipes connected to the starting tile at (1, 1) close a loop"
+        );
+    }
+
+    #[test]
+    fn test_extra_pipe_at_start() {
+        let diagram = Diagram::from_str(".....\n-S-7.\n.|.|.\n.L-J.\n.....").unwrap();
+        assert_eq!(diagram.starting_tile_type().unwrap(), TileType::SouthEast);
+        assert_eq!(diagram.run().unwrap().len(), 8);
+        let (loops, errors) = diagram.loops();
+        assert_eq!(loops.len(), 1);
+        let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec!["the pipe at (0, 1) leads to (1, 1), which doesn't connect back"]
+        );
+    }
+
+    #[test]
+    fn test_unconnected_start() {
+        let diagram = Diagram::from_str("...\n.S.\n...").unwrap();
+        assert!(diagram.run().is_err());
+        assert!(diagram.starting_tile_type().is_err());
     }
 }
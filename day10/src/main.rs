@@ -1,11 +1,47 @@
-use util::Cli;
+use std::str::FromStr;
+
+use common::Diagram;
+use util::clap::{self, Args};
 
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// Draw the main loop and the tiles it encloses, with their count, rather than the answers
+    #[arg(long)]
+    render: bool,
+    /// List every loop of the diagram, with where the other chains of pipes break, skipping the answers
+    #[arg(long)]
+    loops: bool,
+}
+
 fn main() {
-    let cli = Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if cli.extras.render {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let diagram = Diagram::from_str(&data).unwrap();
+        print!("{}", diagram.render().unwrap());
+        println!("enclosed={}", diagram.enclosed().unwrap().len());
+        return;
+    }
+    if cli.extras.loops {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let (loops, errors) = Diagram::from_str(&data).unwrap().loops();
+        for pipe_loop in loops {
+            let (farthest, distance) = pipe_loop.farthest();
+            println!(
+                "start={:?} length={} farthest={farthest:?} distance={distance}",
+                pipe_loop.tiles[0],
+                pipe_loop.len()
+            );
+        }
+        for e in errors {
+            println!("broken: {e}");
+        }
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
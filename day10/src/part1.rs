@@ -5,7 +5,7 @@ use crate::common::Diagram;
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let diagram = Diagram::from_str(&data).unwrap();
-    let visit = diagram.run();
+    let visit = diagram.run().unwrap();
     let farthest = visit.len() / 2;
     farthest.to_string()
 }
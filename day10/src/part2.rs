@@ -1,23 +1,17 @@
 use std::str::FromStr;
 
+use anyhow::Error;
+use util::geometry::Polygon;
+
 use crate::common::Diagram;
 
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let coords = Diagram::from_str(&data).unwrap().run();
-    let area = calculate_area(&coords);
-    let boundary = coords.len();
-    let ans = area - (boundary / 2) + 1; // pick theorem to calculate interior points from the are and the boundary points
-    ans.to_string()
+    enclosed_tiles(&data).unwrap().to_string()
 }
 
-fn calculate_area(coords: &[(usize, usize)]) -> usize {
-    // Shoelace formula
-    let mut area = 0;
-    for i in 0..coords.len() {
-        let next_i = (i + 1) % coords.len();
-        area +=
-            (coords[i].0 * coords[next_i].1) as isize - (coords[i].1 * coords[next_i].0) as isize;
-    }
-    (area / 2).unsigned_abs()
+/// Tiles enclosed by the main loop, from Pick's theorem
+fn enclosed_tiles(data: &str) -> Result<i128, Error> {
+    let coords = Diagram::from_str(data)?.run()?;
+    Polygon::new(coords)?.interior_points()
 }
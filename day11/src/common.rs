@@ -31,22 +31,40 @@ impl TryFrom<char> for SpaceType {
     }
 }
 
+/// Pair of galaxies, with the distance between them once the universe expanded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GalaxyPair {
+    pub galaxies: ((usize, usize), (usize, usize)),
+    pub distance: usize,
+}
+
+#[derive(Debug)]
+pub struct Report {
+    pub sum: usize,
+    pub nearest: GalaxyPair,
+    pub farthest: GalaxyPair,
+}
+
 #[derive(Debug)]
-pub struct Image(Grid<SpaceType>);
+pub struct Image {
+    grid: Grid<SpaceType>,
+    /// Number of space expansion rows above each row
+    expansion_rows_before: Vec<usize>,
+    /// Number of space expansion columns left of each column
+    expansion_columns_before: Vec<usize>,
+}
 impl FromStr for Image {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let v = s
             .lines()
-            .map(|line| {
-                let v: Vec<SpaceType> = line
-                    .chars()
-                    .map(|c| SpaceType::try_from(c).unwrap())
-                    .collect();
-                v
-            })
-            .collect();
-        let mut image = Self(Grid::new(v));
+            .map(|line| line.chars().map(SpaceType::try_from).collect())
+            .collect::<Result<_, _>>()?;
+        let mut image = Self {
+            grid: Grid::new(v),
+            expansion_rows_before: vec![],
+            expansion_columns_before: vec![],
+        };
         image.expand();
         Ok(image)
     }
@@ -54,14 +72,14 @@ impl FromStr for Image {
 impl Image {
     pub fn expand(&mut self) {
         let rows_index_with_no_galaxies: Vec<usize> = self
-            .0
+            .grid
             .rows()
             .enumerate()
             .filter(|(_i, row)| row.iter().all(|s| *s == SpaceType::EmptySpace))
             .map(|(i, _)| i)
             .collect();
         let columns_index_with_no_galaxies: Vec<usize> = self
-            .0
+            .grid
             .columns_vec()
             .into_iter()
             .enumerate()
@@ -69,67 +87,146 @@ impl Image {
             .map(|(i, _)| i)
             .collect();
 
+        self.expansion_rows_before =
+            Self::prefix_counts(&rows_index_with_no_galaxies, self.grid.nb_rows());
+        self.expansion_columns_before =
+            Self::prefix_counts(&columns_index_with_no_galaxies, self.grid.nb_columns());
+
         for row_index in rows_index_with_no_galaxies.into_iter() {
-            self.0.replace_row(
+            self.grid.replace_row(
                 row_index,
-                vec![SpaceType::SpaceExpansion; self.0.nb_columns()],
+                vec![SpaceType::SpaceExpansion; self.grid.nb_columns()],
             );
         }
 
         for column_index in columns_index_with_no_galaxies.into_iter() {
-            self.0.replace_column(
+            self.grid.replace_column(
                 column_index,
-                vec![SpaceType::SpaceExpansion; self.0.nb_rows()],
+                vec![SpaceType::SpaceExpansion; self.grid.nb_rows()],
             )
         }
     }
 
-    pub fn run<const EXPAND_FACTOR: usize>(&self) -> usize {
-        let galaxies = self.0.find_all(&SpaceType::Galaxy);
-        let pairs =
-            (0..galaxies.len() - 1).flat_map(|i| (i + 1..galaxies.len()).map(move |j| (i, j)));
-        pairs
-            .map(|(g1, g2)| {
-                let (g1x, g1y) = galaxies[g1];
-                let (g2x, g2y) = galaxies[g2];
-                let dx = (g1x as isize - g2x as isize).abs();
-                let dy = (g1y as isize - g2y as isize).abs();
-                let nb_space_expansion =
-                    self.nb_space_expansion_crossed(galaxies[g1], galaxies[g2]);
-                (dx + dy) as usize + nb_space_expansion * (EXPAND_FACTOR - 1)
+    /// For each index below `len`, the number of `indexes` before it
+    fn prefix_counts(indexes: &[usize], len: usize) -> Vec<usize> {
+        let mut counts = Vec::with_capacity(len);
+        let mut count = 0;
+        for i in 0..len {
+            counts.push(count);
+            if indexes.contains(&i) {
+                count += 1;
+            }
+        }
+        counts
+    }
+
+    /// Position of the galaxies once every space expansion row and column is `expand_factor` wide
+    fn expanded_galaxies(&self, expand_factor: usize) -> Result<Vec<(usize, usize)>, Error> {
+        if expand_factor == 0 {
+            return Err(anyhow!("the expansion factor must be at least 1"));
+        }
+        Ok(self
+            .grid
+            .find_all(&SpaceType::Galaxy)
+            .into_iter()
+            .map(|(x, y)| {
+                (
+                    x + self.expansion_columns_before[x] * (expand_factor - 1),
+                    y + self.expansion_rows_before[y] * (expand_factor - 1),
+                )
             })
-            .sum()
+            .collect())
     }
 
-    fn nb_space_expansion_crossed(
-        &self,
-        (p1x, p1y): (usize, usize),
-        (p2x, p2y): (usize, usize),
-    ) -> usize {
-        let nb_space_expansion_rows = if p2y > p1y {
-            (p1y..p2y)
-                .filter(|&y| self.0.get((p1x, y)).unwrap() == &SpaceType::SpaceExpansion)
-                .count()
-        } else {
-            (p2y..p1y)
-                .filter(|&y| self.0.get((p1x, y)).unwrap() == &SpaceType::SpaceExpansion)
-                .count()
-        };
+    /// Every pair of `galaxies`, with their distance between their `expanded` positions
+    fn pairs<'a>(
+        galaxies: &'a [(usize, usize)],
+        expanded: &'a [(usize, usize)],
+    ) -> impl Iterator<Item = GalaxyPair> + 'a {
+        (0..galaxies.len()).flat_map(move |i| {
+            (i + 1..galaxies.len()).map(move |j| GalaxyPair {
+                galaxies: (galaxies[i], galaxies[j]),
+                distance: expanded[i].0.abs_diff(expanded[j].0)
+                    + expanded[i].1.abs_diff(expanded[j].1),
+            })
+        })
+    }
 
-        let nb_space_expansion_columns = if p2x > p1x {
-            (p1x..p2x)
-                .filter(|&x| self.0.get((x, p1y)).unwrap() == &SpaceType::SpaceExpansion)
-                .count()
-        } else {
-            (p2x..p1x)
-                .filter(|&x| self.0.get((x, p1y)).unwrap() == &SpaceType::SpaceExpansion)
-                .count()
+    pub fn run(&self, expand_factor: usize) -> Result<usize, Error> {
+        let galaxies = self.grid.find_all(&SpaceType::Galaxy);
+        let expanded = self.expanded_galaxies(expand_factor)?;
+        Ok(Self::pairs(&galaxies, &expanded)
+            .map(|pair| pair.distance)
+            .sum())
+    }
+
+    /// Sum of the distances, along with the nearest and the farthest pairs of galaxies. None if there are less
+    /// than 2 galaxies.
+    pub fn report(&self, expand_factor: usize) -> Result<Option<Report>, Error> {
+        let galaxies = self.grid.find_all(&SpaceType::Galaxy);
+        let expanded = self.expanded_galaxies(expand_factor)?;
+        let mut pairs = Self::pairs(&galaxies, &expanded);
+        let Some(first) = pairs.next() else {
+            return Ok(None);
+        };
+        let init = Report {
+            sum: first.distance,
+            nearest: first,
+            farthest: first,
         };
-        nb_space_expansion_rows + nb_space_expansion_columns
+        Ok(Some(pairs.fold(init, |mut report, pair| {
+            report.sum += pair.distance;
+            if pair.distance < report.nearest.distance {
+                report.nearest = pair;
+            }
+            if pair.distance > report.farthest.distance {
+                report.farthest = pair;
+            }
+            report
+        })))
     }
 }
 impl Display for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+        write!(f, "{}", self.grid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+
+    #[test]
+    fn test_expand_factors() {
+        let image = Image::from_str(EXAMPLE).unwrap();
+        assert_eq!(image.run(2).unwrap(), 374);
+        assert_eq!(image.run(10).unwrap(), 1030);
+        assert_eq!(image.run(100).unwrap(), 8410);
+        assert_eq!(image.run(1).unwrap(), 292);
+        let err = image.run(0).unwrap_err();
+        assert_eq!(err.to_string(), "the expansion factor must be at least 1");
+    }
+
+    #[test]
+    fn test_report() {
+        let image = Image::from_str(EXAMPLE).unwrap();
+        let report = image.report(2).unwrap().unwrap();
+        assert_eq!(report.sum, 374);
+        // the first pair found wins ties
+        assert_eq!(report.nearest.galaxies, ((7, 1), (6, 4)));
+        assert_eq!(report.nearest.distance, 5);
+        assert_eq!(report.farthest.galaxies, ((7, 1), (0, 9)));
+        assert_eq!(report.farthest.distance, 19);
     }
 }
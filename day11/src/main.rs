@@ -1,11 +1,44 @@
-use util::Cli;
+use std::str::FromStr;
+
+use common::Image;
+use util::clap::{self, Args};
 
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// How many times larger the empty rows and columns grow in part 2
+    #[arg(long, default_value_t = 1_000_000)]
+    expand_factor: usize,
+    /// Print the sum, the nearest and the farthest pairs of galaxies at the part 2 expansion factor, in place of
+    /// the answers
+    #[arg(long)]
+    report: bool,
+}
+
 fn main() {
-    let cli = Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if cli.extras.report {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let image = Image::from_str(&data).unwrap();
+        match image.report(cli.extras.expand_factor).unwrap() {
+            Some(report) => {
+                println!("sum={}", report.sum);
+                println!(
+                    "nearest={:?} distance={}",
+                    report.nearest.galaxies, report.nearest.distance
+                );
+                println!(
+                    "farthest={:?} distance={}",
+                    report.farthest.galaxies, report.farthest.distance
+                );
+            }
+            None => println!("less than 2 galaxies"),
+        }
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
-    println!("part2={}", part2::run(&cli.file));
+    println!("part2={}", part2::run(&cli.file, cli.extras.expand_factor));
 }
@@ -5,6 +5,6 @@ use crate::common::Image;
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let image = Image::from_str(&data).unwrap();
-    let ans = image.run::<2>();
+    let ans = image.run(2).unwrap();
     ans.to_string()
 }
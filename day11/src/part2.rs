@@ -2,9 +2,9 @@ use std::str::FromStr;
 
 use crate::common::Image;
 
-pub fn run(file: &str) -> String {
+pub fn run(file: &str, expand_factor: usize) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let image = Image::from_str(&data).unwrap();
-    let ans = image.run::<1000000>();
+    let ans = image.run(expand_factor).unwrap();
     ans.to_string()
 }
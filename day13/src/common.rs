@@ -1,4 +1,4 @@
-use std::{fmt::Display, ops::Range, str::FromStr};
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Error, Ok};
 use util::grid::Grid;
@@ -30,16 +30,11 @@ impl AsRef<Vec<Pattern>> for Patterns {
     }
 }
 impl Patterns {
-    pub fn reflections_score(&self) -> usize {
+    /// Sum of the scores of the reflections found with exactly `smudges` smudges in each pattern
+    pub fn reflections_score(&self, smudges: usize) -> usize {
         self.0
             .iter()
-            .map(|pattern| pattern.find_reflection().score())
-            .sum()
-    }
-    pub fn reflections_score_smudge(&self) -> usize {
-        self.0
-            .iter()
-            .map(|pattern| pattern.find_smudge().score())
+            .map(|pattern| pattern.find_reflection(smudges).score())
             .sum()
     }
 }
@@ -61,93 +56,51 @@ impl AsRef<Grid<Element>> for Pattern {
     }
 }
 impl Pattern {
-    pub fn find_reflection(&self) -> Reflection {
-        if let Some(n) = (1..self.0.nb_rows()).find(|&i| {
-            let (up, down) = Self::calculate_ranges(i, self.0.nb_rows());
-            let mut up_slice = self.0.rows_slice(up.start, up.end);
-            up_slice.reverse();
-            let down_slice = self.0.rows_slice(down.start, down.end);
-            up_slice == down_slice
-        }) {
-            return Reflection::Horizontal(n);
-        }
-
-        if let Some(n) = (1..self.0.nb_columns()).find(|&i| {
-            let (left, right) = Self::calculate_ranges(i, self.0.nb_columns());
-            let mut left_slice = self.0.columns_slice(left.start, left.end);
-            left_slice.reverse();
-            let right_slice = self.0.columns_slice(right.start, right.end);
-            left_slice == right_slice
-        }) {
-            return Reflection::Vertical(n);
-        }
+    /// Every candidate axis, horizontal ones first, with the cells that don't match their mirror image. The
+    /// cells are reported on the upper or left side of the axis.
+    pub fn reflections(&self) -> Vec<Reflection> {
+        let (nb_columns, nb_rows) = (self.0.nb_columns(), self.0.nb_rows());
+        let horizontal = (1..nb_rows).map(|axis| Reflection::Horizontal {
+            axis,
+            smudges: Self::mismatches(axis, nb_rows, nb_columns, |(x, y)| self.0.get((x, y))),
+        });
+        let vertical = (1..nb_columns).map(|axis| Reflection::Vertical {
+            axis,
+            smudges: Self::mismatches(axis, nb_columns, nb_rows, |(y, x)| self.0.get((x, y)))
+                .into_iter()
+                .map(|(y, x)| (x, y))
+                .collect(),
+        });
+        horizontal.chain(vertical).collect()
+    }
 
-        Reflection::NotFound
+    /// The first reflection which needs exactly `smudges` cells to be fixed
+    pub fn find_reflection(&self, smudges: usize) -> Reflection {
+        self.reflections()
+            .into_iter()
+            .find(|reflection| reflection.smudges().len() == smudges)
+            .unwrap_or(Reflection::NotFound)
     }
-    pub fn find_smudge(&self) -> Reflection {
-        for i in 1..self.0.nb_rows() {
-            let (up_range, down_range) = Self::calculate_ranges(i, self.0.nb_rows());
-            let mut up_slice = self.0.rows_slice(up_range.start, up_range.end);
-            up_slice.reverse();
-            let down_slice = self.0.rows_slice(down_range.start, down_range.end);
-            for row in 0..up_slice.len() {
-                if up_slice[row] != down_slice[row]
-                    && up_slice[row]
-                        .iter()
-                        .zip(down_slice[row].iter())
-                        .filter(|(up, down)| up != down)
-                        .count()
-                        == 1
-                {
-                    let mut up_slice_mod = up_slice.clone();
-                    for col in 0..up_slice[row].len() {
-                        if up_slice[row][col] != down_slice[row][col] {
-                            up_slice_mod[row][col].smudge();
-                            break;
-                        }
-                    }
-                    if up_slice_mod == down_slice {
-                        return Reflection::Horizontal(i);
-                    }
-                }
-            }
-        }
 
-        for i in 1..self.0.nb_columns() {
-            let (left_range, right_range) = Self::calculate_ranges(i, self.0.nb_columns());
-            let mut left_slice = self.0.columns_slice(left_range.start, left_range.end);
-            left_slice.reverse();
-            let right_slice = self.0.columns_slice(right_range.start, right_range.end);
-            for col in 0..left_range.len() {
-                if left_slice[col] != right_slice[col]
-                    && left_slice[col]
-                        .iter()
-                        .zip(right_slice[col].iter())
-                        .filter(|(left, right)| left != right)
-                        .count()
-                        == 1
-                {
-                    let mut left_slice_mod = left_slice.clone();
-                    for row in 0..left_slice[col].len() {
-                        if left_slice[col][row] != right_slice[col][row] {
-                            left_slice_mod[col][row].smudge();
-                            break;
-                        }
-                    }
-                    if left_slice_mod == right_slice {
-                        return Reflection::Vertical(i);
-                    }
+    /// Cells mirrored by an axis placed before line `axis` that differ from their image, over `len` lines of
+    /// `width` cells. `get` takes (position in line, line) coordinates.
+    fn mismatches<'a>(
+        axis: usize,
+        len: usize,
+        width: usize,
+        get: impl Fn((usize, usize)) -> Option<&'a Element>,
+    ) -> Vec<(usize, usize)> {
+        let span = axis.min(len - axis);
+        let mut mismatches = vec![];
+        for d in 0..span {
+            let (before, after) = (axis - 1 - d, axis + d);
+            for i in 0..width {
+                if get((i, before)) != get((i, after)) {
+                    mismatches.push((i, before));
                 }
             }
         }
-        Reflection::NotFound
-    }
-
-    fn calculate_ranges(i: usize, m: usize) -> (Range<usize>, Range<usize>) {
-        let span = i.min(m - i);
-        let up = if i > m / 2 { (i - span)..i } else { 0..i };
-        let down = if i > m / 2 { i..m } else { i..(2 * span) };
-        (up, down)
+        mismatches
     }
 }
 
@@ -156,14 +109,6 @@ pub enum Element {
     Ash,
     Rock,
 }
-impl Element {
-    fn smudge(&mut self) {
-        match self {
-            Self::Ash => *self = Self::Rock,
-            Self::Rock => *self = Self::Ash,
-        }
-    }
-}
 impl TryFrom<char> for Element {
     type Error = Error;
     fn try_from(value: char) -> Result<Self, Self::Error> {
@@ -183,17 +128,82 @@ impl Display for Element {
     }
 }
 
+/// A line of reflection, given by the number of rows above it or of columns left of it. The smudges are the
+/// cells that would need fixing for the reflection to be perfect.
+#[derive(Debug, PartialEq)]
 pub enum Reflection {
-    Horizontal(usize),
-    Vertical(usize),
+    Horizontal {
+        axis: usize,
+        smudges: Vec<(usize, usize)>,
+    },
+    Vertical {
+        axis: usize,
+        smudges: Vec<(usize, usize)>,
+    },
     NotFound,
 }
 impl Reflection {
     pub fn score(&self) -> usize {
         match self {
-            Self::Horizontal(n) => *n * 100,
-            Self::Vertical(n) => *n,
+            Self::Horizontal { axis, .. } => *axis * 100,
+            Self::Vertical { axis, .. } => *axis,
             Self::NotFound => 0,
         }
     }
+
+    pub fn smudges(&self) -> &[(usize, usize)] {
+        match self {
+            Self::Horizontal { smudges, .. } | Self::Vertical { smudges, .. } => smudges,
+            Self::NotFound => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.
+
+#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#";
+
+    #[test]
+    fn test_example() {
+        let patterns = Patterns::from_str(EXAMPLE).unwrap();
+        assert_eq!(patterns.reflections_score(0), 405);
+        assert_eq!(patterns.reflections_score(1), 400);
+    }
+
+    #[test]
+    fn test_smudges() {
+        let patterns = Patterns::from_str(EXAMPLE).unwrap();
+        let pattern = &patterns.as_ref()[0];
+        assert_eq!(
+            pattern.find_reflection(0),
+            Reflection::Vertical {
+                axis: 5,
+                smudges: vec![]
+            }
+        );
+        assert_eq!(
+            pattern.find_reflection(1),
+            Reflection::Horizontal {
+                axis: 3,
+                smudges: vec![(0, 0)]
+            }
+        );
+        assert_eq!(pattern.reflections().len(), 6 + 8);
+    }
 }
@@ -5,6 +5,6 @@ use crate::common::Patterns;
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let patterns = Patterns::from_str(&data).unwrap();
-    let ans = patterns.reflections_score_smudge();
+    let ans = patterns.reflections_score(1);
     ans.to_string()
 }
@@ -1,4 +1,4 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashMap, fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Error};
 use util::grid::Grid;
@@ -31,9 +31,22 @@ impl Display for Element {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    North,
+    West,
+    South,
+    East,
+}
+impl Direction {
+    const ALL: [Direction; 4] = [Self::North, Self::West, Self::South, Self::East];
+}
+
 pub struct Platform {
     grid: Grid<Element>,
-    rounded_rocks: Vec<(usize, usize)>,
+    /// For each direction, the runs of tiles between cube shaped rocks (or the edges) the rounded rocks roll
+    /// along, each ordered from the edge the rocks roll toward
+    blocks: [Vec<Vec<(usize, usize)>>; 4],
 }
 impl FromStr for Platform {
     type Err = Error;
@@ -42,125 +55,105 @@ impl FromStr for Platform {
             .lines()
             .map(|line| line.chars().map(Element::try_from).collect())
             .collect();
-        let v = v?;
-        let rounded_rocks = v
-            .iter()
-            .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
-                    .enumerate()
-                    .filter(|(_, &e)| e == Element::RoundedRock)
-                    .map(move |(x, _)| (x, y))
-            })
-            .collect();
-
-        Ok(Self {
-            grid: Grid::new(v),
-            rounded_rocks,
-        })
+        let grid = Grid::new(v?);
+        let blocks = Direction::ALL.map(|direction| Self::blocks(&grid, direction));
+        Ok(Self { grid, blocks })
     }
 }
 impl Platform {
-    pub fn tilt_north(&mut self) {
-        loop {
-            let mut stable = true;
-            for (x, y) in self.rounded_rocks.iter_mut() {
-                if *y == 0 {
-                    continue;
-                }
+    /// Split every line the rocks roll along when tilting toward `direction` at the cube shaped rocks
+    fn blocks(grid: &Grid<Element>, direction: Direction) -> Vec<Vec<(usize, usize)>> {
+        let (nb_columns, nb_rows) = (grid.nb_columns(), grid.nb_rows());
+        let lines: Vec<Vec<(usize, usize)>> = match direction {
+            Direction::North => (0..nb_columns)
+                .map(|x| (0..nb_rows).map(|y| (x, y)).collect())
+                .collect(),
+            Direction::South => (0..nb_columns)
+                .map(|x| (0..nb_rows).rev().map(|y| (x, y)).collect())
+                .collect(),
+            Direction::West => (0..nb_rows)
+                .map(|y| (0..nb_columns).map(|x| (x, y)).collect())
+                .collect(),
+            Direction::East => (0..nb_rows)
+                .map(|y| (0..nb_columns).rev().map(|x| (x, y)).collect())
+                .collect(),
+        };
+        lines
+            .iter()
+            .flat_map(|line| {
+                line.split(|&pos| grid.get(pos) == Some(&Element::CubeShapedRock))
+                    .filter(|block| !block.is_empty())
+                    .map(|block| block.to_vec())
+            })
+            .collect()
+    }
 
-                if let Some(e) = self.grid.get((*x, *y - 1)) {
-                    if *e == Element::EmptySpace {
-                        self.grid.set((*x, *y), Element::EmptySpace);
-                        *y -= 1;
-                        self.grid.set((*x, *y), Element::RoundedRock);
-                        stable = false;
-                    }
-                }
-            }
-            if stable {
-                return;
+    pub fn tilt(&mut self, direction: Direction) {
+        let Self { grid, blocks } = self;
+        for block in blocks[direction as usize].iter() {
+            let nb_rounded_rocks = block
+                .iter()
+                .filter(|&&pos| grid.get(pos) == Some(&Element::RoundedRock))
+                .count();
+            for (i, &pos) in block.iter().enumerate() {
+                let element = if i < nb_rounded_rocks {
+                    Element::RoundedRock
+                } else {
+                    Element::EmptySpace
+                };
+                grid.set(pos, element);
             }
         }
     }
-    pub fn tilt_west(&mut self) {
-        loop {
-            let mut stable = true;
-            for (x, y) in self.rounded_rocks.iter_mut() {
-                if *x == 0 {
-                    continue;
-                }
 
-                if let Some(e) = self.grid.get((*x - 1, *y)) {
-                    if *e == Element::EmptySpace {
-                        self.grid.set((*x, *y), Element::EmptySpace);
-                        *x -= 1;
-                        self.grid.set((*x, *y), Element::RoundedRock);
-                        stable = false;
-                    }
-                }
-            }
-            if stable {
-                return;
-            }
+    pub fn run_cycle(&mut self) {
+        for direction in Direction::ALL {
+            self.tilt(direction);
         }
     }
-    pub fn tilt_east(&mut self) {
-        loop {
-            let mut stable = true;
-            for (x, y) in self.rounded_rocks.iter_mut() {
-                if *x == self.grid.nb_columns() - 1 {
-                    continue;
-                }
 
-                if let Some(e) = self.grid.get((*x + 1, *y)) {
-                    if *e == Element::EmptySpace {
-                        self.grid.set((*x, *y), Element::EmptySpace);
-                        *x += 1;
-                        self.grid.set((*x, *y), Element::RoundedRock);
-                        stable = false;
-                    }
+    /// Run `nb_cycles` spin cycles. Once the platform comes back to a state it was already in, the remaining
+    /// cycles are skipped but for the ones left over after the last full period.
+    pub fn spin(&mut self, nb_cycles: usize) {
+        let mut seen = HashMap::new();
+        let mut cycle = 0;
+        while cycle < nb_cycles {
+            if let Some(previous) = seen.insert(self.state(), cycle) {
+                let period = cycle - previous;
+                let remaining = (nb_cycles - cycle) % period;
+                for _ in 0..remaining {
+                    self.run_cycle();
                 }
-            }
-            if stable {
                 return;
             }
+            self.run_cycle();
+            cycle += 1;
         }
     }
-    pub fn tilt_south(&mut self) {
-        loop {
-            let mut stable = true;
-            for (x, y) in self.rounded_rocks.iter_mut() {
-                if *y == self.grid.nb_rows() - 1 {
-                    continue;
-                }
 
-                if let Some(e) = self.grid.get((*x, *y + 1)) {
-                    if *e == Element::EmptySpace {
-                        self.grid.set((*x, *y), Element::EmptySpace);
-                        *y += 1;
-                        self.grid.set((*x, *y), Element::RoundedRock);
-                        stable = false;
-                    }
-                }
-            }
-            if stable {
-                return;
+    /// Positions of the rounded rocks packed as bits, cheap to hash and compare
+    pub fn state(&self) -> Vec<u64> {
+        let mut bits = vec![0; (self.grid.nb_columns() * self.grid.nb_rows()).div_ceil(64)];
+        for (i, element) in self.grid.iter().enumerate() {
+            if *element == Element::RoundedRock {
+                bits[i / 64] |= 1 << (i % 64);
             }
         }
+        bits
     }
 
-    pub fn run_cycle(&mut self) {
-        self.tilt_north();
-        self.tilt_west();
-        self.tilt_south();
-        self.tilt_east()
-    }
-
-    pub fn calculate_score(&self) -> usize {
-        self.rounded_rocks
-            .iter()
-            .map(|(_, y)| (self.grid.nb_rows() - y))
+    /// Total load of the rounded rocks on the edge toward `direction`
+    pub fn load(&self, direction: Direction) -> usize {
+        let (nb_columns, nb_rows) = (self.grid.nb_columns(), self.grid.nb_rows());
+        self.grid
+            .find_all(&Element::RoundedRock)
+            .into_iter()
+            .map(|(x, y)| match direction {
+                Direction::North => nb_rows - y,
+                Direction::South => y + 1,
+                Direction::West => nb_columns - x,
+                Direction::East => x + 1,
+            })
             .sum()
     }
 }
@@ -176,3 +169,39 @@ fn test_range() {
         println!("{i}");
     }
 }
+
+#[test]
+fn test_example() {
+    let s = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+    let mut platform = Platform::from_str(s).unwrap();
+    platform.tilt(Direction::North);
+    assert_eq!(platform.load(Direction::North), 136);
+
+    let mut platform = Platform::from_str(s).unwrap();
+    platform.run_cycle();
+    assert_eq!(
+        platform.to_string(),
+        ".....#....
+....#...O#
+...OO##...
+.OO#......
+.....OOO#.
+.O#...O#.#
+....O#....
+......OOOO
+#...O###..
+#..OO#....
+"
+    );
+    platform.spin(1_000_000_000 - 1);
+    assert_eq!(platform.load(Direction::North), 64);
+}
@@ -1,9 +1,20 @@
-use std::str::FromStr;
+use std::{fmt::Display, io::BufRead, str::FromStr};
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
+/// HASH of a step or of a label, computed with the HASH algorithm of the puzzle
 pub struct Step(usize);
 impl Step {
+    pub fn new(s: &str) -> Self {
+        let mut current_value = 0;
+        for c in s.chars() {
+            current_value += c as usize;
+            current_value *= 17;
+            current_value %= 256;
+        }
+        Self(current_value)
+    }
+
     pub fn inner(&self) -> usize {
         self.0
     }
@@ -12,13 +23,7 @@ impl Step {
 impl FromStr for Step {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut current_value = 0;
-        for c in s.chars() {
-            current_value += c as usize;
-            current_value *= 17;
-            current_value %= 256;
-        }
-        Ok(Self(current_value))
+        Ok(Self::new(s))
     }
 }
 
@@ -26,35 +31,256 @@ type Label = String;
 
 #[derive(Debug)]
 pub enum Operation {
-    Remove { box_id: usize, label: Label },
-    Add { box_id: usize, lens: Lens },
+    Remove { label: Label },
+    Add { lens: Lens },
 }
 impl FromStr for Operation {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.ends_with('-') {
-            let label = s.split('-').next().unwrap();
-            let box_id = Step::from_str(label)?.inner();
-            Ok(Self::Remove {
-                box_id,
-                label: label.to_string(),
-            })
-        } else {
-            let mut sp = s.split('=');
-            let label = sp.next().unwrap();
-            let box_id = Step::from_str(label)?.inner();
-            let focal_len = sp.next().unwrap().parse()?;
-            let lens = Lens {
-                label: label.to_string(),
-                focal_len,
-            };
-            Ok(Self::Add { lens, box_id })
+        let i = s
+            .find(['-', '='])
+            .ok_or(anyhow!("'{s}' has no operation ('-' or '=')"))?;
+        let (label, rest) = s.split_at(i);
+        if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(anyhow!("'{s}' has an invalid label '{label}'"));
+        }
+        let label = label.to_string();
+        match (&rest[..1], &rest[1..]) {
+            ("-", "") => Ok(Self::Remove { label }),
+            ("-", extra) => Err(anyhow!("'{s}' has unexpected '{extra}' after '-'")),
+            (_, "") => Err(anyhow!("'{s}' is missing its focal length")),
+            (_, focal_len) => {
+                let focal_len = focal_len
+                    .parse()
+                    .map_err(|e| anyhow!("'{s}' has an invalid focal length: {e}"))?;
+                Ok(Self::Add {
+                    lens: Lens { label, focal_len },
+                })
+            }
+        }
+    }
+}
+impl Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Remove { label } => write!(f, "{label}-"),
+            Self::Add { lens } => write!(f, "{}={}", lens.label, lens.focal_len),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Lens {
     pub label: Label,
     pub focal_len: usize,
 }
+
+/// A step of the initialization sequence, with the offset of its first byte in the sequence
+#[derive(Debug, PartialEq)]
+pub struct RawStep {
+    pub offset: usize,
+    pub text: String,
+}
+impl RawStep {
+    pub fn operation(&self) -> Result<Operation, Error> {
+        Operation::from_str(&self.text)
+            .map_err(|e| anyhow!("malformed step at offset {}: {e}", self.offset))
+    }
+}
+
+/// Reads the comma separated steps of an initialization sequence one at a time, so that the sequence never
+/// has to be loaded in memory at once. Whitespace and newlines around the steps are ignored.
+pub struct StepReader<R: BufRead> {
+    reader: R,
+    offset: usize,
+    buf: Vec<u8>,
+}
+impl<R: BufRead> StepReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            offset: 0,
+            buf: vec![],
+        }
+    }
+}
+impl<R: BufRead> Iterator for StepReader<R> {
+    type Item = Result<RawStep, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.buf.clear();
+        let offset = self.offset;
+        let len = match self.reader.read_until(b',', &mut self.buf) {
+            Ok(0) => return None,
+            Ok(len) => len,
+            Err(e) => return Some(Err(e.into())),
+        };
+        self.offset += len;
+
+        let at_end = self.buf.last() != Some(&b',');
+        let raw = match std::str::from_utf8(&self.buf) {
+            Ok(raw) => raw.trim_end_matches(','),
+            Err(e) => return Some(Err(anyhow!("malformed step at offset {offset}: {e}"))),
+        };
+        let text = raw.trim();
+        if text.is_empty() {
+            if at_end {
+                // trailing whitespace after the last step
+                return None;
+            }
+            return Some(Err(anyhow!("empty step at offset {offset}")));
+        }
+        // the step starts after the whitespace (e.g. a line break) that trim() removed
+        let offset = offset + raw.len() - raw.trim_start().len();
+        if text.contains(char::is_whitespace) {
+            return Some(Err(anyhow!(
+                "malformed step at offset {offset}: '{text}' contains whitespace"
+            )));
+        }
+        let text = text.to_string();
+        Some(Ok(RawStep { offset, text }))
+    }
+}
+
+const NB_BOXES: usize = 256;
+
+/// The 256 boxes of lenses, each lens going in the box given by the `Step` HASH of its label
+pub struct LensLibrary(Vec<Vec<Lens>>);
+impl Default for LensLibrary {
+    fn default() -> Self {
+        Self(vec![vec![]; NB_BOXES])
+    }
+}
+impl LensLibrary {
+    fn lenses_mut(&mut self, label: &str) -> &mut Vec<Lens> {
+        &mut self.0[Step::new(label).inner()]
+    }
+
+    /// Put `lens` in its box, replacing the lens with the same label if there's one
+    pub fn insert(&mut self, lens: Lens) {
+        let lenses = self.lenses_mut(&lens.label);
+        match lenses.iter_mut().find(|l| l.label == lens.label) {
+            Some(l) => *l = lens,
+            None => lenses.push(lens),
+        }
+    }
+
+    pub fn remove(&mut self, label: &str) -> Option<Lens> {
+        let lenses = self.lenses_mut(label);
+        let i = lenses.iter().position(|l| l.label == label)?;
+        Some(lenses.remove(i))
+    }
+
+    /// The lens labeled `label`, if it's in its box
+    pub fn get(&self, label: &str) -> Option<&Lens> {
+        self.0[Step::new(label).inner()]
+            .iter()
+            .find(|l| l.label == label)
+    }
+
+    pub fn apply(&mut self, operation: Operation) {
+        match operation {
+            Operation::Add { lens } => self.insert(lens),
+            Operation::Remove { label } => {
+                self.remove(&label);
+            }
+        }
+    }
+
+    pub fn focusing_power(&self) -> usize {
+        self.0
+            .iter()
+            .enumerate()
+            .flat_map(|(box_id, lenses)| {
+                lenses
+                    .iter()
+                    .enumerate()
+                    .map(move |(slot, lens)| (box_id + 1) * (slot + 1) * lens.focal_len)
+            })
+            .sum()
+    }
+}
+/// Non-empty boxes, as printed by the puzzle's example
+impl Display for LensLibrary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (box_id, lenses) in self.0.iter().enumerate() {
+            if lenses.is_empty() {
+                continue;
+            }
+            write!(f, "Box {box_id}:")?;
+            for lens in lenses.iter() {
+                write!(f, " [{} {}]", lens.label, lens.focal_len)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    #[test]
+    fn test_step_reader() {
+        let data = "rn=1,cm-,\nqp=3, cm=2\n";
+        let steps = StepReader::new(data.as_bytes())
+            .map(|step| step.unwrap().text)
+            .collect::<Vec<_>>();
+        assert_eq!(steps, vec!["rn=1", "cm-", "qp=3", "cm=2"]);
+        let offsets = StepReader::new("rn=1,\nqp=3, cm=2".as_bytes())
+            .map(|step| step.unwrap().offset)
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec![0, 6, 12]);
+        let err = StepReader::new("rn=1,c m=2".as_bytes())
+            .nth(1)
+            .unwrap()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "malformed step at offset 5: 'c m=2' contains whitespace"
+        );
+        let hash_sum: usize = StepReader::new(EXAMPLE.as_bytes())
+            .map(|step| Step::new(&step.unwrap().text).inner())
+            .sum();
+        assert_eq!(hash_sum, 1320);
+    }
+
+    #[test]
+    fn test_malformed_steps() {
+        let errors = StepReader::new("rn=1,cm,qp=,ab=x,,pc-".as_bytes())
+            .filter_map(|step| match step {
+                Ok(step) => step.operation().err(),
+                Err(e) => Some(e),
+            })
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            vec![
+                "malformed step at offset 5: 'cm' has no operation ('-' or '=')",
+                "malformed step at offset 8: 'qp=' is missing its focal length",
+                "malformed step at offset 12: 'ab=x' has an invalid focal length: invalid digit found in string",
+                "empty step at offset 17",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_example() {
+        let mut library = LensLibrary::default();
+        let mut trace = String::new();
+        for step in EXAMPLE.split(',') {
+            library.apply(Operation::from_str(step).unwrap());
+            trace.push_str(&format!("After \"{step}\":\n{library}\n"));
+        }
+        assert!(trace
+            .starts_with("After \"rn=1\":\nBox 0: [rn 1]\n\nAfter \"cm-\":\nBox 0: [rn 1]\n\n"));
+        assert!(trace
+            .ends_with("After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n\n"));
+        assert_eq!(library.focusing_power(), 145);
+        assert_eq!(library.get("ab").map(|lens| lens.focal_len), Some(5));
+        assert_eq!(library.remove("qp"), None);
+    }
+}
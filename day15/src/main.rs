@@ -1,62 +1,60 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{fs::File, io::BufReader, str::FromStr};
 
-use common::{Lens, Operation, Step};
+use anyhow::{Context, Error};
+use common::{LensLibrary, Step, StepReader};
+use util::clap::{self, Args};
 
 mod common;
 
+#[derive(Args)]
+struct Options {
+    /// Print the boxes after each step of the initialization sequence, as in the puzzle's example
+    #[arg(long)]
+    trace: bool,
+    /// Label of a lens whose focal length to print once the sequence is done
+    #[arg(long)]
+    lens: Vec<String>,
+}
+
 fn main() {
-    util::run!();
+    util::run!(Options);
 }
 
-fn part1(file: &str) -> String {
-    let data = std::fs::read_to_string(file).unwrap();
-    let ans: usize = data
-        .split(',')
-        .map(|ss| Step::from_str(ss).unwrap().inner())
-        .sum();
-    ans.to_string()
+fn read_steps(file: &str) -> Result<StepReader<BufReader<File>>, Error> {
+    let file = File::open(file).with_context(|| format!("can't open '{file}'"))?;
+    Ok(StepReader::new(BufReader::new(file)))
+}
+
+fn part1(file: &str, _: &Options) -> String {
+    hash_sum(file).unwrap().to_string()
+}
+
+fn hash_sum(file: &str) -> Result<usize, Error> {
+    read_steps(file)?.try_fold(
+        0,
+        |sum, step| Ok(sum + Step::from_str(&step?.text)?.inner()),
+    )
+}
+
+fn part2(file: &str, options: &Options) -> String {
+    let library = initialize(file, options.trace).unwrap();
+    for label in &options.lens {
+        match library.get(label) {
+            Some(lens) => println!("lens {label}: focal length {}", lens.focal_len),
+            None => println!("lens {label}: not in any box"),
+        }
+    }
+    library.focusing_power().to_string()
 }
 
-pub fn part2(file: &str) -> String {
-    let data = std::fs::read_to_string(file).unwrap();
-
-    let mut boxes: HashMap<usize, Vec<Lens>> = HashMap::new();
-
-    data.split(',').for_each(|step| {
-        let op = Operation::from_str(step).unwrap();
-
-        match op {
-            Operation::Add { lens, box_id } => {
-                if let Some(b) = boxes.get_mut(&box_id) {
-                    if let Some(i) = b.iter().position(|innerlens| lens.label == innerlens.label) {
-                        b[i] = lens;
-                    } else {
-                        b.push(lens);
-                    }
-                } else {
-                    boxes.insert(box_id, vec![lens]);
-                }
-            }
-            Operation::Remove { box_id, label } => {
-                if let Some(b) = boxes.get_mut(&box_id) {
-                    if let Some(i) = b.iter().position(|lens| lens.label == label) {
-                        b.remove(i);
-                    }
-                }
-            }
+fn initialize(file: &str, trace: bool) -> Result<LensLibrary, Error> {
+    let mut library = LensLibrary::default();
+    for step in read_steps(file)? {
+        let step = step?;
+        library.apply(step.operation()?);
+        if trace {
+            println!("After \"{}\":\n{library}", step.text);
         }
-    });
-
-    let ans: usize = boxes
-        .iter()
-        .map(|(box_id, lens)| {
-            let box_value = box_id + 1;
-            lens.iter()
-                .enumerate()
-                .map(|(i, lens)| box_value * (i + 1) * lens.focal_len)
-                .sum::<usize>()
-        })
-        .sum();
-
-    ans.to_string()
+    }
+    Ok(library)
 }
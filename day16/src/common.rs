@@ -1,372 +1,207 @@
-use std::{collections::HashMap, fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Error};
+use rayon::prelude::*;
 use util::grid::Grid;
 
+use crate::{engine::BeamEngine, optics::Optics, render::Simulation};
+
+/// A tile of the contraption, whose behaviour is described by the `Optics` table.
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Tile {
-    EmptySpace,
-    RightwardMirror,
-    LeftwardMirror,
-    HorizontalSplitter,
-    VerticalSplitter,
-}
+pub struct Tile(char);
+impl Tile {
+    pub const EMPTY_SPACE: Tile = Tile('.');
 
-impl TryFrom<char> for Tile {
-    type Error = Error;
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            '.' => Ok(Self::EmptySpace),
-            '|' => Ok(Self::VerticalSplitter),
-            '-' => Ok(Self::HorizontalSplitter),
-            '/' => Ok(Self::RightwardMirror),
-            '\\' => Ok(Self::LeftwardMirror),
-            _ => Err(anyhow!("fail to convert tile")),
-        }
+    pub fn symbol(&self) -> char {
+        self.0
+    }
+}
+impl From<char> for Tile {
+    fn from(value: char) -> Self {
+        Self(value)
     }
 }
 impl Display for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self {
-            Self::EmptySpace => '.',
-            Self::VerticalSplitter => '|',
-            Self::HorizontalSplitter => '-',
-            Self::RightwardMirror => '/',
-            Self::LeftwardMirror => '\\',
-        };
-        write!(f, "{s}")
+        write!(f, "{}", self.0)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Direction {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
     Up,
     Down,
     Right,
     Left,
 }
+impl Direction {
+    pub const ALL: [Direction; 4] = [Self::Up, Self::Down, Self::Right, Self::Left];
 
-#[derive(Debug, Clone, Copy)]
-pub struct Beam {
-    direction: Direction,
-    pos: (usize, usize),
-}
-impl Beam {
-    fn keep_beam(&self, visit: &Visit) -> bool {
-        match self.direction {
-            Direction::Up => !visit.u,
-            Direction::Down => !visit.d,
-            Direction::Left => !visit.l,
-            Direction::Right => !visit.r,
-        }
+    pub fn index(&self) -> usize {
+        *self as usize
     }
 
-    /// Returns a new beam if the current tile splits the beam in 2, return the original beam if the obstacle
-    /// was not reached with that direction yet
-    fn mutate(mut beam: Beam, grid: &Grid<Tile>) -> Vec<Self> {
-        let tile = grid.get((beam.pos.0, beam.pos.1)).unwrap();
-
-        match tile {
-            Tile::EmptySpace => vec![beam],
-            Tile::HorizontalSplitter => match beam.direction {
-                Direction::Right | Direction::Left => {
-                    vec![beam]
-                }
-                Direction::Up | Direction::Down => {
-                    // Create a new beam that goes to left direction
-                    beam.direction = Direction::Right;
-                    vec![
-                        beam,
-                        Self {
-                            direction: Direction::Left,
-                            pos: beam.pos,
-                        },
-                    ]
-                }
-            },
-            Tile::VerticalSplitter => match beam.direction {
-                Direction::Right | Direction::Left => {
-                    beam.direction = Direction::Down;
-                    vec![
-                        beam,
-                        Self {
-                            direction: Direction::Up,
-                            pos: beam.pos,
-                        },
-                    ]
-                }
-                Direction::Up | Direction::Down => vec![beam],
-            },
-            Tile::LeftwardMirror => {
-                match beam.direction {
-                    Direction::Up => beam.direction = Direction::Left,
-                    Direction::Down => beam.direction = Direction::Right,
-                    Direction::Left => beam.direction = Direction::Up,
-                    Direction::Right => beam.direction = Direction::Down,
-                }
-                vec![beam]
-            }
-            Tile::RightwardMirror => {
-                match beam.direction {
-                    Direction::Up => beam.direction = Direction::Right,
-                    Direction::Down => beam.direction = Direction::Left,
-                    Direction::Left => beam.direction = Direction::Down,
-                    Direction::Right => beam.direction = Direction::Up,
-                }
-                vec![beam]
-            }
+    pub fn arrow(&self) -> char {
+        match self {
+            Self::Up => '^',
+            Self::Down => 'v',
+            Self::Right => '>',
+            Self::Left => '<',
+        }
+    }
+}
+impl FromStr for Direction {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" | "^" => Ok(Self::Up),
+            "down" | "v" => Ok(Self::Down),
+            "right" | ">" => Ok(Self::Right),
+            "left" | "<" => Ok(Self::Left),
+            _ => Err(anyhow!("fail to convert direction '{s}'")),
         }
     }
 }
 
-// determines if a beam already reached the obstacle with the direction
-#[derive(Debug, Clone, Copy, Default)]
-struct Visit {
-    u: bool,
-    d: bool,
-    l: bool,
-    r: bool,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Beam {
+    pub direction: Direction,
+    pub pos: (usize, usize),
 }
+impl Beam {
+    /// Returns the beams leaving the current tile: none if the tile absorbs the beam, several if it splits it
+    pub fn mutate(beam: Beam, grid: &Grid<Tile>, optics: &Optics) -> Vec<Self> {
+        let tile = *grid.get(beam.pos).unwrap();
+        optics
+            .outgoing(tile, beam.direction)
+            .iter()
+            .map(|&direction| Self { direction, ..beam })
+            .collect()
+    }
 
-impl Visit {
-    fn update(&mut self, direction: Direction) {
-        match direction {
-            Direction::Up => self.u = true,
-            Direction::Down => self.d = true,
-            Direction::Left => self.l = true,
-            Direction::Right => self.r = true,
-        }
+    /// returns None if the beam is exiting the grid
+    pub fn advance(&self, grid: &Grid<Tile>) -> Option<Beam> {
+        let (x, y) = self.pos;
+        let pos = match self.direction {
+            Direction::Up => (x, y.checked_sub(1)?),
+            Direction::Down => (x, y + 1),
+            Direction::Left => (x.checked_sub(1)?, y),
+            Direction::Right => (x + 1, y),
+        };
+        grid.get(pos).map(|_| Beam { pos, ..*self })
+    }
+}
+/// Parse a beam written as `x,y,direction`, e.g. `0,0,right`
+impl FromStr for Beam {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut split = s.split(',');
+        let mut next = || {
+            split
+                .next()
+                .ok_or(anyhow!("expected x,y,direction, got '{s}'"))
+        };
+        let x = next()?.trim().parse()?;
+        let y = next()?.trim().parse()?;
+        let direction = next()?.trim().parse()?;
+        Ok(Self {
+            direction,
+            pos: (x, y),
+        })
     }
 }
 
-//6921
 pub struct Puzzle {
     grid: Grid<Tile>,
-    energized: Grid<bool>,
-    visits: HashMap<(usize, usize), Visit>,
+    optics: Optics,
 }
 impl Puzzle {
-    pub fn initial_beams_part1(&self) -> Vec<Vec<Beam>> {
-        vec![vec![Beam {
+    pub fn initial_beams_part1(&self) -> Vec<Beam> {
+        vec![Beam {
             pos: (0, 0),
             direction: Direction::Right,
-        }]]
+        }]
     }
-    pub fn initial_beams_part2(&self) -> Vec<Vec<Beam>> {
-        let mut top_beams: Vec<Vec<Beam>> = (1..self.grid.nb_columns() - 2)
-            .map(|x| {
-                vec![Beam {
-                    pos: (x, 0),
-                    direction: Direction::Down,
-                }]
-            })
-            .collect();
-        let mut down_beams: Vec<Vec<Beam>> = (1..self.grid.nb_columns() - 2)
-            .map(|x| {
-                vec![Beam {
-                    pos: (x, self.grid.nb_rows() - 1),
-                    direction: Direction::Up,
-                }]
-            })
-            .collect();
-        let mut left_beams: Vec<Vec<Beam>> = (1..self.grid.nb_rows() - 2)
-            .map(|y| {
-                vec![Beam {
-                    pos: (0, y),
-                    direction: Direction::Right,
-                }]
-            })
-            .collect();
-        let mut right_beams: Vec<Vec<Beam>> = (1..self.grid.nb_rows() - 2)
-            .map(|y| {
-                vec![Beam {
-                    pos: (self.grid.nb_columns() - 1, y),
-                    direction: Direction::Left,
-                }]
-            })
-            .collect();
-        let mut corners = vec![
-            // top-left
-            vec![Beam {
-                pos: (0, 0),
-                direction: Direction::Right,
-            }],
-            vec![Beam {
-                pos: (0, 0),
-                direction: Direction::Down,
-            }],
-            // Top-right
-            vec![Beam {
-                pos: (self.grid.nb_columns() - 1, 0),
-                direction: Direction::Left,
-            }],
-            vec![Beam {
-                pos: (self.grid.nb_columns() - 1, 0),
-                direction: Direction::Down,
-            }],
-            // Bottom-right
-            vec![Beam {
-                pos: (self.grid.nb_columns() - 1, self.grid.nb_rows() - 1),
-                direction: Direction::Left,
-            }],
-            vec![Beam {
-                pos: (self.grid.nb_columns() - 1, self.grid.nb_rows() - 1),
-                direction: Direction::Up,
-            }],
-            //Bottom-left
-            vec![Beam {
-                pos: (0, self.grid.nb_rows() - 1),
-                direction: Direction::Right,
-            }],
-            vec![Beam {
-                pos: (0, self.grid.nb_rows() - 1),
-                direction: Direction::Up,
-            }],
-        ];
-        corners.append(&mut top_beams);
-        corners.append(&mut down_beams);
-        corners.append(&mut right_beams);
-        corners.append(&mut left_beams);
-        corners
+
+    /// Every edge tile, with the beam heading into the grid. Corners are entered from both of their sides.
+    pub fn initial_beams_part2(&self) -> Vec<Beam> {
+        let (nb_columns, nb_rows) = (self.grid.nb_columns(), self.grid.nb_rows());
+        let top = (0..nb_columns).map(|x| Beam {
+            pos: (x, 0),
+            direction: Direction::Down,
+        });
+        let bottom = (0..nb_columns).map(|x| Beam {
+            pos: (x, nb_rows - 1),
+            direction: Direction::Up,
+        });
+        let left = (0..nb_rows).map(|y| Beam {
+            pos: (0, y),
+            direction: Direction::Right,
+        });
+        let right = (0..nb_rows).map(|y| Beam {
+            pos: (nb_columns - 1, y),
+            direction: Direction::Left,
+        });
+        top.chain(bottom).chain(left).chain(right).collect()
     }
 
-    pub fn run(&mut self, part1: bool) -> usize {
+    pub fn run(&self, part1: bool) -> usize {
         let initial_beams = if part1 {
             self.initial_beams_part1()
         } else {
             self.initial_beams_part2()
         };
+        let engine = BeamEngine::new(&self.grid, &self.optics);
         initial_beams
-            .into_iter()
-            .map(|mut beams| {
-                self.energized.assign(false); //reset to not energized
-                self.clear_visits();
-
-                loop {
-                    // for each beam position energize the grid
-                    beams.iter().map(|beam| beam.pos).for_each(|(x, y)| {
-                        self.energized.set((x, y), true);
-                    });
-
-                    // Change the direction of the beams according to the current standing tile, and spawn new beams if
-                    // splitters were encountered
-                    beams = beams
-                        .iter()
-                        .flat_map(|&beam| Beam::mutate(beam, &self.grid))
-                        .collect();
-
-                    // remove beams that already visited the path
-                    beams = beams
-                        .into_iter()
-                        .filter(|beam| {
-                            let tile = self.grid.get(beam.pos).unwrap();
-                            if *tile != Tile::EmptySpace {
-                                beam.keep_beam(&self.visits[&beam.pos])
-                            } else {
-                                true
-                            }
-                        })
-                        .collect::<Vec<_>>();
-
-                    // update obstacle states
-                    beams.iter().for_each(|beam| {
-                        let tile = self.grid.get(beam.pos).unwrap();
-                        if *tile != Tile::EmptySpace {
-                            self.visits
-                                .get_mut(&beam.pos)
-                                .unwrap()
-                                .update(beam.direction);
-                        }
-                    });
-
-                    // Move all the beams
-                    beams = beams.iter().filter_map(|beam| self.mv(beam)).collect();
-
-                    // if there's no beam, then we're done
-                    if beams.is_empty() {
-                        return self.count_energized_tiles();
-                    }
-                }
-            })
+            .par_iter()
+            .map(|&beam| engine.energized(beam).len())
             .max()
             .unwrap()
     }
-    /// returns None if the beam is exiting the grid
-    fn mv(&self, beam: &Beam) -> Option<Beam> {
-        let (x, y) = beam.pos;
-        let mut new_beam = *beam;
-        match beam.direction {
-            Direction::Up => {
-                if y == 0 {
-                    return None;
-                }
-                new_beam.pos.1 -= 1;
-                Some(new_beam)
-            }
-            Direction::Down => {
-                if y == self.grid.nb_rows() - 1 {
-                    return None;
-                }
-                new_beam.pos.1 += 1;
-                Some(new_beam)
-            }
-            Direction::Left => {
-                if x == 0 {
-                    return None;
-                }
-                new_beam.pos.0 -= 1;
-                Some(new_beam)
-            }
-            Direction::Right => {
-                if x == self.grid.nb_columns() - 1 {
-                    return None;
-                }
-                new_beam.pos.0 += 1;
-                Some(new_beam)
-            }
-        }
-    }
-
-    pub fn clear_visits(&mut self) {
-        self.visits.values_mut().for_each(|v| {
-            v.u = false;
-            v.d = false;
-            v.l = false;
-            v.r = false;
-        })
-    }
 
-    pub fn count_energized_tiles(&self) -> usize {
-        self.energized.find_all(&true).len()
-    }
-}
-impl FromStr for Puzzle {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let v: Result<Vec<Vec<Tile>>, Error> = s
+    /// Parse a contraption made of the elements of `optics`
+    pub fn parse(s: &str, optics: Optics) -> Result<Self, Error> {
+        let v = s
             .lines()
-            .map(|line| line.chars().map(Tile::try_from).collect())
-            .collect();
-        let v = v?;
-        let e = vec![vec![false; v[0].len()]; v.len()];
-        let visits = v
-            .iter()
             .enumerate()
-            .flat_map(|(y, row)| {
-                row.iter()
+            .map(|(y, line)| {
+                line.chars()
+                    .map(Tile::from)
                     .enumerate()
-                    .filter_map(|(x, &col)| {
-                        (col != Tile::EmptySpace).then_some(((x, y), Visit::default()))
+                    .map(|(x, tile)| {
+                        if optics.contains(tile) {
+                            Ok(tile)
+                        } else {
+                            Err(anyhow!("unknown tile '{tile}' at ({x}, {y})"))
+                        }
                     })
-                    .collect::<Vec<_>>()
+                    .collect()
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
         Ok(Self {
             grid: Grid::new(v),
-            energized: Grid::new(e),
-            visits,
+            optics,
         })
     }
+
+    /// Tick by tick simulation of `start` entering the contraption, which must start on a tile of the grid
+    pub fn simulate(&self, start: Beam) -> Result<Simulation<'_>, Error> {
+        if self.grid.get(start.pos).is_none() {
+            return Err(anyhow!(
+                "the beam starts at {:?}, outside of the {}x{} contraption",
+                start.pos,
+                self.grid.nb_columns(),
+                self.grid.nb_rows()
+            ));
+        }
+        Ok(Simulation::new(&self.grid, &self.optics, start))
+    }
+}
+impl FromStr for Puzzle {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, Optics::default())
+    }
 }
 impl Display for Puzzle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use util::grid::Grid;
+
+use crate::{
+    common::{Beam, Direction, Tile},
+    optics::Optics,
+};
+
+/// Set of tiles of the contraption, one bit per tile.
+#[derive(Debug, Clone)]
+pub struct TileSet {
+    bits: Vec<u64>,
+    nb_columns: usize,
+}
+impl TileSet {
+    fn new(nb_columns: usize, nb_rows: usize) -> Self {
+        Self {
+            bits: vec![0; (nb_columns * nb_rows).div_ceil(64)],
+            nb_columns,
+        }
+    }
+
+    fn insert(&mut self, (x, y): (usize, usize)) {
+        let i = y * self.nb_columns + x;
+        self.bits[i / 64] |= 1 << (i % 64);
+    }
+
+    fn union_with(&mut self, other: &TileSet) {
+        self.bits
+            .iter_mut()
+            .zip(other.bits.iter())
+            .for_each(|(a, b)| *a |= b);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+}
+
+/// Walk `beam` through transparent tiles, adding the tiles crossed to `tiles`. Returns the beam reaching the next
+/// optical element, or None if the beam leaves the grid first.
+fn segment(
+    grid: &Grid<Tile>,
+    optics: &Optics,
+    mut beam: Beam,
+    tiles: &mut TileSet,
+) -> Option<Beam> {
+    loop {
+        if !optics.is_transparent(*grid.get(beam.pos).unwrap()) {
+            return Some(beam);
+        }
+        tiles.insert(beam.pos);
+        beam = beam.advance(grid)?;
+    }
+}
+
+/// Beam propagation through the contraption, precomputed once so that any starting beam is resolved without
+/// simulating it.
+///
+/// A node is a beam reaching an optical element (any tile that is not transparent) from a given direction. Each node
+/// leads to the next nodes through straight segments of transparent tiles, so the contraption is a graph of nodes.
+/// The tiles energized from a node are the same for every node of its strongly connected component, and
+/// are memoised per component.
+pub struct BeamEngine<'a> {
+    grid: &'a Grid<Tile>,
+    optics: &'a Optics,
+    node_ids: HashMap<Beam, usize>,
+    /// Component of each node
+    components: Vec<usize>,
+    /// Tiles energized by a beam reaching any node of the component
+    energized: Vec<TileSet>,
+}
+impl<'a> BeamEngine<'a> {
+    pub fn new(grid: &'a Grid<Tile>, optics: &'a Optics) -> Self {
+        let new_tiles = || TileSet::new(grid.nb_columns(), grid.nb_rows());
+
+        let nodes: Vec<Beam> = grid
+            .rows()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &tile)| !optics.is_transparent(tile))
+                    .map(move |(x, _)| x)
+                    .map(move |x| (x, y))
+            })
+            .flat_map(|pos| Direction::ALL.map(|direction| Beam { direction, pos }))
+            .collect();
+        let node_ids: HashMap<Beam, usize> =
+            nodes.iter().enumerate().map(|(i, &b)| (b, i)).collect();
+
+        // Segments leaving each node
+        let mut tiles = Vec::with_capacity(nodes.len());
+        let mut edges = Vec::with_capacity(nodes.len());
+        for &node in nodes.iter() {
+            let mut node_tiles = new_tiles();
+            node_tiles.insert(node.pos);
+            let next = Beam::mutate(node, grid, optics)
+                .into_iter()
+                .filter_map(|beam| beam.advance(grid))
+                .filter_map(|beam| segment(grid, optics, beam, &mut node_tiles))
+                .map(|beam| node_ids[&beam])
+                .collect::<Vec<_>>();
+            tiles.push(node_tiles);
+            edges.push(next);
+        }
+
+        let mut tarjan = Tarjan::new(&edges);
+        (0..nodes.len()).for_each(|node| {
+            if tarjan.index[node].is_none() {
+                tarjan.visit(node);
+            }
+        });
+
+        // Components come out of Tarjan's algorithm in reverse topological order, so the components reachable
+        // from a component are always complete when it is reached.
+        let mut energized: Vec<TileSet> = Vec::with_capacity(tarjan.sccs.len());
+        for (c, members) in tarjan.sccs.iter().enumerate() {
+            let mut component_tiles = new_tiles();
+            for &node in members {
+                component_tiles.union_with(&tiles[node]);
+                for &next in edges[node].iter() {
+                    let next_component = tarjan.components[next];
+                    if next_component != c {
+                        component_tiles.union_with(&energized[next_component]);
+                    }
+                }
+            }
+            energized.push(component_tiles);
+        }
+
+        Self {
+            grid,
+            optics,
+            node_ids,
+            components: tarjan.components,
+            energized,
+        }
+    }
+
+    /// Tiles energized by `beam` entering the grid
+    pub fn energized(&self, beam: Beam) -> TileSet {
+        let mut tiles = TileSet::new(self.grid.nb_columns(), self.grid.nb_rows());
+        if let Some(node) = segment(self.grid, self.optics, beam, &mut tiles) {
+            let component = self.components[self.node_ids[&node]];
+            tiles.union_with(&self.energized[component]);
+        }
+        tiles
+    }
+}
+
+/// Tarjan's strongly connected components algorithm over the node graph.
+struct Tarjan<'a> {
+    edges: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low_link: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next_index: usize,
+    components: Vec<usize>,
+    sccs: Vec<Vec<usize>>,
+}
+impl<'a> Tarjan<'a> {
+    fn new(edges: &'a [Vec<usize>]) -> Self {
+        let n = edges.len();
+        Self {
+            edges,
+            index: vec![None; n],
+            low_link: vec![0; n],
+            on_stack: vec![false; n],
+            stack: Vec::new(),
+            next_index: 0,
+            components: vec![0; n],
+            sccs: Vec::new(),
+        }
+    }
+
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next_index);
+        self.low_link[node] = self.next_index;
+        self.next_index += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        for &next in self.edges[node].iter() {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.low_link[node] = self.low_link[node].min(self.low_link[next]);
+                }
+                Some(next_index) if self.on_stack[next] => {
+                    self.low_link[node] = self.low_link[node].min(next_index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low_link[node]) == self.index[node] {
+            let mut members = Vec::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                self.on_stack[member] = false;
+                self.components[member] = self.sccs.len();
+                members.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            self.sccs.push(members);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, str::FromStr};
+
+    use super::*;
+    use crate::common::Puzzle;
+
+    const EXAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    /// Plain beam by beam simulation, used as a reference
+    fn simulate(grid: &Grid<Tile>, optics: &Optics, start: Beam) -> usize {
+        let mut seen = HashSet::new();
+        let mut beams = vec![start];
+        while let Some(beam) = beams.pop() {
+            if seen.insert(beam) {
+                beams.extend(
+                    Beam::mutate(beam, grid, optics)
+                        .into_iter()
+                        .filter_map(|b| b.advance(grid)),
+                );
+            }
+        }
+        seen.iter().map(|b| b.pos).collect::<HashSet<_>>().len()
+    }
+
+    #[test]
+    fn test_example() {
+        let puzzle = Puzzle::from_str(EXAMPLE).unwrap();
+        assert_eq!(puzzle.run(true), 46);
+        assert_eq!(puzzle.run(false), 51);
+    }
+
+    #[test]
+    fn test_matches_simulation() {
+        let puzzle = Puzzle::from_str(EXAMPLE).unwrap();
+        let grid = Grid::new(
+            EXAMPLE
+                .lines()
+                .map(|line| line.chars().map(Tile::from).collect())
+                .collect(),
+        );
+        let optics = Optics::default();
+        let engine = BeamEngine::new(&grid, &optics);
+        for beam in puzzle.initial_beams_part2() {
+            assert_eq!(
+                engine.energized(beam).len(),
+                simulate(&grid, &optics, beam),
+                "{beam:?}"
+            );
+        }
+    }
+}
@@ -1,21 +1,88 @@
-use std::str::FromStr;
+use std::{str::FromStr, thread, time::Duration};
 
-use common::Puzzle;
+use anyhow::{Context, Error};
+use common::{Beam, Puzzle};
+use optics::Optics;
+use render::RenderMode;
+use util::clap::{self, Args};
 
 mod common;
+mod engine;
+mod optics;
+mod render;
 
-fn main() {
-    util::run!();
+#[derive(Args)]
+struct Options {
+    /// Starting beam `x,y,direction` (e.g. `0,0,right`) to draw the propagation of, skipping the answers
+    #[arg(long)]
+    render: Option<Beam>,
+    /// How to draw the contraption: energized, beams or ansi
+    #[arg(long, default_value = "beams")]
+    mode: RenderMode,
+    /// Draw every tick of the propagation, waiting this many milliseconds between them
+    #[arg(long)]
+    animate: Option<u64>,
+    /// File describing custom optical elements, added to the mirrors and splitters of the puzzle
+    #[arg(long)]
+    optics: Option<String>,
 }
 
-fn part1(file: &str) -> String {
-    let data = std::fs::read_to_string(file).unwrap();
-    let mut puzzle = Puzzle::from_str(&data).unwrap();
+fn main() -> Result<(), Error> {
+    let cli = util::Cli::<Options>::with_extras();
+    let options = &cli.extras;
+    let optics = match &options.optics {
+        Some(file) => std::fs::read_to_string(file)
+            .map_err(Error::from)
+            .and_then(|s| Optics::from_str(&s))
+            .with_context(|| format!("invalid optics file '{file}'"))?,
+        None => Optics::default(),
+    };
+    let data = std::fs::read_to_string(&cli.file)
+        .with_context(|| format!("can't read input file '{}'", cli.file))?;
+    let puzzle = Puzzle::parse(&data, optics)
+        .with_context(|| format!("invalid contraption in '{}'", cli.file))?;
+    match options.render {
+        Some(start) => render(&puzzle, start, options.mode, options.animate)?,
+        None => {
+            println!("part1={}", part1(&puzzle));
+            println!("part2={}", part2(&puzzle));
+        }
+    }
+    Ok(())
+}
+
+fn part1(puzzle: &Puzzle) -> String {
     puzzle.run(true).to_string()
 }
 
-fn part2(file: &str) -> String {
-    let data = std::fs::read_to_string(file).unwrap();
-    let mut puzzle = Puzzle::from_str(&data).unwrap();
+fn part2(puzzle: &Puzzle) -> String {
     puzzle.run(false).to_string()
 }
+
+fn render(
+    puzzle: &Puzzle,
+    start: Beam,
+    mode: RenderMode,
+    animate: Option<u64>,
+) -> Result<(), Error> {
+    let mut simulation = puzzle.simulate(start)?;
+    if let Some(delay) = animate {
+        loop {
+            if mode == RenderMode::Ansi {
+                // clear the terminal so that the frames are drawn over each other
+                print!("\x1b[2J\x1b[H");
+            }
+            println!("tick={}", simulation.tick());
+            println!("{}", simulation.render(mode));
+            if !simulation.step() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(delay));
+        }
+    } else {
+        simulation.run();
+        print!("{}", simulation.render(mode));
+    }
+    println!("energized={}", simulation.count_energized_tiles());
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, Error};
+
+use crate::common::{Direction, Tile};
+
+/// Behaviour of the tiles of the puzzle, written in the same format as the files given to `Optics::load`.
+const DEFAULT_RULES: &str = r".
+/ up:right down:left left:down right:up
+\ up:left down:right left:up right:down
+| left:up,down right:up,down
+- up:left,right down:left,right";
+
+/// Directions leaving a tile, for a beam reaching it in each of the 4 directions (indexed by `Direction::index`)
+type Element = [Vec<Direction>; 4];
+
+/// Table of optical elements: what each tile symbol does to the beams reaching it.
+#[derive(Debug, Clone)]
+pub struct Optics(HashMap<char, Element>);
+impl Optics {
+    /// Add the elements described by `s` to the table, replacing the ones with the same symbol.
+    ///
+    /// Each line describes an element, starting with its symbol followed by `incoming:outgoing,...` rules.
+    /// Beams reaching the element from a direction without a rule go through it, and an empty list of outgoing
+    /// directions absorbs the beam. For instance `> left:` is a one-way gate that stops beams heading left, and
+    /// `+ right:up,right,down` splits beams heading right three ways.
+    pub fn load(&mut self, s: &str) -> Result<(), Error> {
+        for (i, line) in s.lines().enumerate() {
+            let err = |msg: String| anyhow!("line {}: {msg}", i + 1);
+            let mut tokens = line.split_whitespace();
+            let Some(symbol) = tokens.next() else {
+                continue;
+            };
+            let mut chars = symbol.chars();
+            let (Some(symbol), None) = (chars.next(), chars.next()) else {
+                return Err(err(format!("symbol '{symbol}' is not a single character")));
+            };
+
+            let mut element: Element = Direction::ALL.map(|direction| vec![direction]);
+            for rule in tokens {
+                let (incoming, outgoing) = rule
+                    .split_once(':')
+                    .ok_or_else(|| err(format!("expected incoming:outgoing, got '{rule}'")))?;
+                let incoming = Direction::from_str(incoming).map_err(|e| err(e.to_string()))?;
+                element[incoming.index()] = outgoing
+                    .split(',')
+                    .filter(|d| !d.is_empty())
+                    .map(Direction::from_str)
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| err(e.to_string()))?;
+            }
+            self.0.insert(symbol, element);
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, tile: Tile) -> bool {
+        self.0.contains_key(&tile.symbol())
+    }
+
+    /// Directions leaving `tile` for a beam reaching it heading `direction`
+    pub fn outgoing(&self, tile: Tile, direction: Direction) -> &[Direction] {
+        &self.0[&tile.symbol()][direction.index()]
+    }
+
+    /// Whether beams go through `tile` unchanged, whatever their direction
+    pub fn is_transparent(&self, tile: Tile) -> bool {
+        Direction::ALL
+            .iter()
+            .all(|&direction| self.outgoing(tile, direction) == [direction])
+    }
+}
+impl Default for Optics {
+    fn default() -> Self {
+        let mut optics = Self(HashMap::new());
+        optics.load(DEFAULT_RULES).unwrap();
+        optics
+    }
+}
+/// The default elements, extended with the ones described by `s`
+impl FromStr for Optics {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut optics = Self::default();
+        optics.load(s)?;
+        Ok(optics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::{Beam, Puzzle};
+
+    #[test]
+    fn test_custom_elements() {
+        let optics =
+            Optics::from_str("x up: down: left: right:\n> left:\n+ right:up,right,down").unwrap();
+        assert!(optics.is_transparent(Tile::from('.')));
+        assert!(!optics.is_transparent(Tile::from('>')));
+
+        // The three way splitter sends the beam up, right and down
+        let puzzle = Puzzle::parse("...\n.+.\n...", optics.clone()).unwrap();
+        let mut simulation = puzzle
+            .simulate(Beam::from_str("0,1,right").unwrap())
+            .unwrap();
+        simulation.run();
+        assert_eq!(simulation.count_energized_tiles(), 5);
+
+        // The gate only stops the beams heading left
+        let puzzle = Puzzle::parse("..>..", optics.clone()).unwrap();
+        let mut simulation = puzzle
+            .simulate(Beam::from_str("4,0,left").unwrap())
+            .unwrap();
+        simulation.run();
+        assert_eq!(simulation.count_energized_tiles(), 3);
+        assert_eq!(puzzle.run(false), 5);
+
+        let puzzle = Puzzle::parse("..x..", optics).unwrap();
+        assert_eq!(puzzle.run(true), 3);
+        assert_eq!(puzzle.run(false), 3);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let err = Optics::from_str("x right:\nxy right:").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: symbol 'xy' is not a single character"
+        );
+        let err = Optics::from_str("x north:up").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: fail to convert direction 'north'");
+    }
+}
@@ -0,0 +1,191 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Error};
+use util::grid::Grid;
+
+use crate::{
+    common::{Beam, Direction, Tile},
+    optics::Optics,
+};
+
+const ANSI_ENERGIZED: &str = "\x1b[1;33m";
+const ANSI_BEAM_HEAD: &str = "\x1b[1;31m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// Energized tiles as `#`, everything else as `.`
+    Energized,
+    /// The contraption with the beams drawn over empty space, as in the puzzle's examples
+    Beams,
+    /// Same as `Beams`, with energized tiles and the beam heads coloured
+    Ansi,
+}
+impl FromStr for RenderMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "energized" => Ok(Self::Energized),
+            "beams" => Ok(Self::Beams),
+            "ansi" => Ok(Self::Ansi),
+            _ => Err(anyhow!(
+                "unknown render mode '{s}', expected energized, beams or ansi"
+            )),
+        }
+    }
+}
+
+/// Tick by tick propagation of the beams, moving every beam by one tile per tick.
+pub struct Simulation<'a> {
+    grid: &'a Grid<Tile>,
+    optics: &'a Optics,
+    /// Beams that reached a new tile during the last tick
+    beams: Vec<Beam>,
+    /// Every beam seen so far, as the tile it reached and the direction it reached it from
+    seen: HashSet<Beam>,
+    tick: usize,
+}
+impl<'a> Simulation<'a> {
+    pub fn new(grid: &'a Grid<Tile>, optics: &'a Optics, start: Beam) -> Self {
+        Self {
+            grid,
+            optics,
+            beams: vec![start],
+            seen: HashSet::from([start]),
+            tick: 0,
+        }
+    }
+
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    /// Move every beam by one tile. Returns false once all the beams left the grid or follow a known path.
+    pub fn step(&mut self) -> bool {
+        let beams = self
+            .beams
+            .iter()
+            .flat_map(|&beam| Beam::mutate(beam, self.grid, self.optics))
+            .filter_map(|beam| beam.advance(self.grid))
+            .filter(|&beam| self.seen.insert(beam))
+            .collect::<Vec<_>>();
+        self.beams = beams;
+        self.tick += 1;
+        !self.beams.is_empty()
+    }
+
+    pub fn run(&mut self) {
+        while self.step() {}
+    }
+
+    pub fn count_energized_tiles(&self) -> usize {
+        self.seen
+            .iter()
+            .map(|beam| beam.pos)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    pub fn render(&self, mode: RenderMode) -> String {
+        let mut directions: HashMap<(usize, usize), Vec<Direction>> = HashMap::new();
+        for beam in self.seen.iter() {
+            directions.entry(beam.pos).or_default().push(beam.direction);
+        }
+        let heads: HashSet<(usize, usize)> = self.beams.iter().map(|beam| beam.pos).collect();
+
+        let mut s = String::new();
+        for (y, row) in self.grid.rows().enumerate() {
+            for (x, tile) in row.iter().enumerate() {
+                let beams = directions.get(&(x, y));
+                let c = match (mode, beams) {
+                    (RenderMode::Energized, Some(_)) => '#',
+                    (RenderMode::Energized, None) => '.',
+                    (_, Some(beams)) if *tile == Tile::EMPTY_SPACE => match beams.as_slice() {
+                        [direction] => direction.arrow(),
+                        beams => char::from_digit(beams.len() as u32, 10).unwrap(),
+                    },
+                    _ => tile.symbol(),
+                };
+                match (mode, beams.is_some()) {
+                    (RenderMode::Ansi, _) if heads.contains(&(x, y)) => {
+                        s.push_str(&format!("{ANSI_BEAM_HEAD}{c}{ANSI_RESET}"))
+                    }
+                    (RenderMode::Ansi, true) => {
+                        s.push_str(&format!("{ANSI_ENERGIZED}{c}{ANSI_RESET}"))
+                    }
+                    _ => s.push(c),
+                }
+            }
+            s.push('\n');
+        }
+        s
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::common::Puzzle;
+
+    const EXAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    #[test]
+    fn test_render_example() {
+        let puzzle = Puzzle::from_str(EXAMPLE).unwrap();
+        let err = puzzle
+            .simulate(Beam::from_str("10,0,left").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "the beam starts at (10, 0), outside of the 10x10 contraption"
+        );
+        let mut simulation = puzzle
+            .simulate(Beam::from_str("0,0,right").unwrap())
+            .unwrap();
+        simulation.run();
+        assert_eq!(simulation.count_energized_tiles(), 46);
+        assert_eq!(
+            simulation.render(RenderMode::Beams),
+            r">|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..
+"
+        );
+        assert_eq!(
+            simulation.render(RenderMode::Energized),
+            "######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..
+"
+        );
+    }
+}
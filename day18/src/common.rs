@@ -1,6 +1,9 @@
-use std::str::FromStr;
+use std::{collections::HashMap, fmt::Write, str::FromStr};
 
 use anyhow::{anyhow, Error};
+use util::geometry::Polygon;
+
+const ANSI_RESET: &str = "\x1b[0m";
 
 #[derive(Debug, Clone, Copy)]
 enum Direction {
@@ -9,6 +12,17 @@ enum Direction {
     Right(isize),
     Left(isize),
 }
+impl Direction {
+    /// Unit step of the direction, in screen coordinates (y grows downward), and the number of meters
+    fn step(&self) -> ((isize, isize), isize) {
+        match *self {
+            Self::Up(n) => ((0, -1), n),
+            Self::Down(n) => ((0, 1), n),
+            Self::Right(n) => ((1, 0), n),
+            Self::Left(n) => ((-1, 0), n),
+        }
+    }
+}
 impl FromStr for Direction {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -49,12 +63,49 @@ impl TryFrom<Color> for Direction {
 
 #[derive(Debug, Clone)]
 struct Color(String);
+impl Color {
+    fn rgb(&self) -> Result<(u8, u8, u8), Error> {
+        let hex = self.0.trim_start_matches('#');
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .ok_or(anyhow!("color '{}' is too short", self.0))
+                .and_then(|c| Ok(u8::from_str_radix(c, 16)?))
+        };
+        Ok((channel(0)?, channel(2)?, channel(4)?))
+    }
+}
 impl FromStr for Color {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self(s.replace(')', "")))
+        Ok(Self(s.trim().replace(')', "")))
+    }
+}
+
+/// A straight edge of the lagoon, between two corners
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: (isize, isize),
+    end: (isize, isize),
+}
+impl Segment {
+    fn bounds(&self) -> ((isize, isize), (isize, isize)) {
+        (
+            (self.start.0.min(self.end.0), self.start.0.max(self.end.0)),
+            (self.start.1.min(self.end.1), self.start.1.max(self.end.1)),
+        )
+    }
+
+    /// First and last points shared by the two segments. Both being horizontal or vertical, what they share is
+    /// the overlap of their bounding boxes.
+    fn intersection(&self, other: &Segment) -> Option<((isize, isize), (isize, isize))> {
+        let ((ax0, ax1), (ay0, ay1)) = self.bounds();
+        let ((bx0, bx1), (by0, by1)) = other.bounds();
+        let first = (ax0.max(bx0), ay0.max(by0));
+        let last = (ax1.min(bx1), ay1.min(by1));
+        (first.0 <= last.0 && first.1 <= last.1).then_some((first, last))
     }
 }
+
 struct Edge {
     dir: Direction,
     color: Color,
@@ -64,68 +115,223 @@ pub struct Puzzle(Vec<Edge>);
 impl FromStr for Puzzle {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let edges: Vec<Edge> = s
+        let edges = s
             .lines()
-            .map(|line| {
+            .enumerate()
+            .map(|(i, line)| {
                 let mut ss = line.split('(');
-                let dir = Direction::from_str(ss.next().unwrap()).unwrap();
-                let color = Color::from_str(ss.next().unwrap()).unwrap();
-                Edge { dir, color }
+                let dir = Direction::from_str(ss.next().unwrap())
+                    .map_err(|e| anyhow!("line {}: {e}", i + 1))?;
+                let color = Color::from_str(ss.next().ok_or(anyhow!("line {}: no color", i + 1))?)?;
+                Ok(Edge { dir, color })
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
         Ok(Self(edges))
     }
 }
 
 impl Puzzle {
-    pub fn run_part1(self) -> isize {
-        let (area, boundary_len) =
-            Self::calculate_area_and_boundary_len(self.0.into_iter().map(|edge| edge.dir));
-        let interiors = Self::calculate_interior(area, boundary_len);
-        boundary_len + interiors
-    }
-    pub fn run_part2(self) -> isize {
-        let (area, boundary_len) = Self::calculate_area_and_boundary_len(
-            self.0
-                .into_iter()
-                .map(|edge| Direction::try_from(edge.color.clone()).unwrap()),
-        );
-        let interiors = Self::calculate_interior(area, boundary_len);
-        boundary_len + interiors
-    }
-
-    fn calculate_area_and_boundary_len(
-        directions: impl Iterator<Item = Direction>,
-    ) -> (isize, isize) {
-        // Green theorem for the area
-        let mut ypos = 0;
-        let mut boundary_len = 0;
-        let mut area = 0;
-        for dir in directions {
-            match dir {
-                Direction::Up(n) => {
-                    ypos += n;
-                    boundary_len += n;
-                }
-                Direction::Down(n) => {
-                    ypos -= n;
-                    boundary_len += n;
+    pub fn run_part1(&self) -> Result<i128, Error> {
+        self.lagoon_size(false)
+    }
+    pub fn run_part2(&self) -> Result<i128, Error> {
+        self.lagoon_size(true)
+    }
+
+    /// Meters of lava held by the trench and its interior
+    fn lagoon_size(&self, part2: bool) -> Result<i128, Error> {
+        self.validate(part2)?;
+        let lagoon = Polygon::new(self.segments(part2)?.iter().map(|segment| segment.start))?;
+        Ok(lagoon.boundary_points()? + lagoon.interior_points()?)
+    }
+
+    /// Directions of the dig plan, read from the colors for part2
+    fn directions(&self, part2: bool) -> Result<Vec<Direction>, Error> {
+        self.0
+            .iter()
+            .map(|edge| {
+                if part2 {
+                    Direction::try_from(edge.color.clone())
+                } else {
+                    Ok(edge.dir)
                 }
-                Direction::Left(n) => {
-                    area -= ypos * n;
-                    boundary_len += n;
+            })
+            .collect()
+    }
+
+    fn segments(&self, part2: bool) -> Result<Vec<Segment>, Error> {
+        let mut pos = (0, 0);
+        self.directions(part2)?
+            .into_iter()
+            .map(|dir| {
+                let ((dx, dy), n) = dir.step();
+                let start = pos;
+                pos = (pos.0 + dx * n, pos.1 + dy * n);
+                Ok(Segment { start, end: pos })
+            })
+            .collect()
+    }
+
+    /// Check that the trench comes back to where it started without crossing or touching itself
+    pub fn validate(&self, part2: bool) -> Result<(), Error> {
+        let segments = self.segments(part2)?;
+        let Some(last) = segments.last() else {
+            return Err(anyhow!("the dig plan is empty"));
+        };
+        if last.end != (0, 0) {
+            return Err(anyhow!(
+                "the trench is not closed, it ends at {:?}",
+                last.end
+            ));
+        }
+
+        let n = segments.len();
+        for (j, b) in segments.iter().enumerate() {
+            for (i, a) in segments[..j].iter().enumerate() {
+                let Some((point, last)) = a.intersection(b) else {
+                    continue;
+                };
+                // Consecutive edges share their corner, and only it
+                let consecutive = j == i + 1 || (i == 0 && j == n - 1);
+                if !consecutive || point != last {
+                    return Err(anyhow!(
+                        "the trench crosses itself at {point:?}: line {} runs into line {}",
+                        j + 1,
+                        i + 1
+                    ));
                 }
-                Direction::Right(n) => {
-                    area += ypos * n;
-                    boundary_len += n;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw the trench dug by the part1 plan, each meter colored as its edge with ANSI true colors
+    pub fn render(&self) -> Result<String, Error> {
+        let mut dug = HashMap::new();
+        for (segment, edge) in self.segments(false)?.iter().zip(self.0.iter()) {
+            let ((dx, dy), n) = edge.dir.step();
+            let rgb = edge.color.rgb()?;
+            for k in 0..=n {
+                dug.insert((segment.start.0 + dx * k, segment.start.1 + dy * k), rgb);
+            }
+        }
+        let (xmin, xmax) = (dug.keys().map(|p| p.0).min(), dug.keys().map(|p| p.0).max());
+        let (ymin, ymax) = (dug.keys().map(|p| p.1).min(), dug.keys().map(|p| p.1).max());
+        let (Some(xmin), Some(xmax), Some(ymin), Some(ymax)) = (xmin, xmax, ymin, ymax) else {
+            return Ok(String::new());
+        };
+
+        let mut s = String::new();
+        for y in ymin..=ymax {
+            for x in xmin..=xmax {
+                match dug.get(&(x, y)) {
+                    Some((r, g, b)) => write!(s, "\x1b[38;2;{r};{g};{b}m#{ANSI_RESET}")?,
+                    None => s.push('.'),
                 }
             }
+            s.push('\n');
+        }
+        Ok(s)
+    }
+
+    /// Draw the lagoon as an SVG image, suited to the huge part2 plans. Edges keep the color of their line.
+    pub fn render_svg(&self, part2: bool) -> Result<String, Error> {
+        let segments = self.segments(part2)?;
+        let xs = segments.iter().map(|s| s.start.0);
+        let ys = segments.iter().map(|s| s.start.1);
+        let (xmin, xmax) = (xs.clone().min().unwrap_or(0), xs.max().unwrap_or(0));
+        let (ymin, ymax) = (ys.clone().min().unwrap_or(0), ys.max().unwrap_or(0));
+
+        let mut s = String::new();
+        writeln!(
+            s,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            xmin - 1,
+            ymin - 1,
+            xmax - xmin + 2,
+            ymax - ymin + 2
+        )?;
+        for (segment, edge) in segments.iter().zip(self.0.iter()) {
+            writeln!(
+                s,
+                r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="2" vector-effect="non-scaling-stroke"/>"#,
+                segment.start.0, segment.start.1, segment.end.0, segment.end.1, edge.color.0
+            )?;
         }
-        (area, boundary_len)
+        writeln!(s, "</svg>")?;
+        Ok(s)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)";
+
+    #[test]
+    fn test_example() {
+        let puzzle = Puzzle::from_str(EXAMPLE).unwrap();
+        assert_eq!(puzzle.run_part1().unwrap(), 62);
+        assert_eq!(puzzle.run_part2().unwrap(), 952408144115);
+    }
+
+    #[test]
+    fn test_validate() {
+        let err = Puzzle::from_str("R 2 (#000000)\nD 2 (#000000)\nL 1 (#000000)")
+            .unwrap()
+            .validate(false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the trench is not closed, it ends at (1, 2)"
+        );
+
+        // A figure eight, whose fourth edge crosses the first one
+        let puzzle = Puzzle::from_str(
+            "R 2 (#000000)\nD 2 (#000000)\nL 1 (#000000)\nU 4 (#000000)\nL 1 (#000000)\nD 2 (#000000)",
+        )
+        .unwrap();
+        let err = puzzle.validate(false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the trench crosses itself at (1, 0): line 4 runs into line 1"
+        );
+
+        // Going back over the previous edge
+        let err = Puzzle::from_str("R 2 (#000000)\nL 2 (#000000)")
+            .unwrap()
+            .validate(false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the trench crosses itself at (0, 0): line 2 runs into line 1"
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let puzzle =
+            Puzzle::from_str("R 1 (#ff0000)\nD 1 (#00ff00)\nL 1 (#0000ff)\nU 1 (#ffffff)").unwrap();
+        let render = puzzle.render().unwrap();
+        assert_eq!(render.lines().count(), 2);
+        assert!(render.starts_with("\x1b[38;2;255;255;255m#"));
+        assert!(render.contains("\x1b[38;2;0;255;0m#"));
 
-    fn calculate_interior(area: isize, boundary_len: isize) -> isize {
-        // The infamous Pick theorem
-        area - (boundary_len / 2) + 1
+        let svg = puzzle.render_svg(false).unwrap();
+        assert!(svg.contains(r##"<line x1="0" y1="0" x2="1" y2="0" stroke="#ff0000""##));
     }
 }
@@ -1,22 +1,49 @@
 use std::str::FromStr;
 
 use common::Puzzle;
+use util::clap::{self, Args};
 
 mod common;
 
+#[derive(Args)]
+struct Options {
+    /// Draw the trench of the part1 plan in the terminal, colored as the dig plan, skipping the answers
+    #[arg(long)]
+    render: bool,
+    /// Write the lagoon of the part2 plan as an SVG image to this file rather than solving
+    #[arg(long)]
+    svg: Option<String>,
+}
+
 fn main() {
-    util::run!();
+    let cli = util::Cli::<Options>::with_extras();
+    let options = &cli.extras;
+    if options.render || options.svg.is_some() {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let puzzle = Puzzle::from_str(&data).unwrap();
+        if options.render {
+            puzzle.validate(false).unwrap();
+            print!("{}", puzzle.render().unwrap());
+        }
+        if let Some(svg) = &options.svg {
+            puzzle.validate(true).unwrap();
+            std::fs::write(svg, puzzle.render_svg(true).unwrap()).unwrap();
+        }
+        return;
+    }
+    println!("part1={}", part1(&cli.file));
+    println!("part2={}", part2(&cli.file));
 }
 
 fn part1(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let puzzle = Puzzle::from_str(&data).unwrap();
-    let ans = puzzle.run_part1();
+    let ans = puzzle.run_part1().unwrap();
     ans.to_string()
 }
 fn part2(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let puzzle = Puzzle::from_str(&data).unwrap();
-    let ans = puzzle.run_part2();
+    let ans = puzzle.run_part2().unwrap();
     ans.to_string()
 }
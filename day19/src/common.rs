@@ -1,4 +1,9 @@
-use std::{cell::RefCell, cmp::Ordering, collections::HashMap, ops::Range, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    ops::Index,
+    str::FromStr,
+};
 
 use anyhow::{anyhow, Error};
 
@@ -6,15 +11,27 @@ use anyhow::{anyhow, Error};
 pub struct Puzzle {
     workflows: Workflows,
     ratings: Ratings,
-    combinations_total: RefCell<usize>,
+    categories: BTreeMap<String, Bounds>,
 }
 impl Puzzle {
+    /// Restrict the values `category` can take when exploring the workflows.
+    pub fn with_bounds(mut self, category: &str, min: usize, max: usize) -> Result<Self, Error> {
+        let bounds = Bounds::new(min, max).ok_or(anyhow!(
+            "empty bounds {min}..={max} for category '{category}'"
+        ))?;
+        *self
+            .categories
+            .get_mut(category)
+            .ok_or(anyhow!("unknown category '{category}'"))? = bounds;
+        Ok(self)
+    }
+
     pub fn run(&self) -> usize {
         let mut total = 0;
         for rating in self.ratings.0.iter() {
             let mut workflow_label = "in".to_string();
             loop {
-                match self.workflows.0[&workflow_label].process(rating) {
+                match self.workflows[workflow_label.as_str()].process(rating) {
                     Part::Accepted => {
                         total += rating.values().sum::<usize>();
                         break;
@@ -32,43 +49,42 @@ impl Puzzle {
     }
 
     pub fn explore(&self) -> usize {
-        let ratings: HashMap<String, Range<usize>> = ["x", "m", "a", "s"]
-            .map(|k| (k.to_string(), 1..4000))
-            .into_iter()
-            .collect();
-        self.explore_workflow(&self.workflows.0["in"], ratings);
-        *self.combinations_total.borrow()
+        let ratings = self.categories.clone();
+        self.explore_workflow(&self.workflows["in"], 0, ratings)
     }
 
-    fn explore_workflow(&self, wf: &Workflow, ratings: HashMap<String, Range<usize>>) {
-        let mut ratings_ok = ratings.clone();
-        let mut ratings_else = ratings.clone();
-        if wf.cmp == Ordering::Less {
-            ratings_ok.get_mut(&wf.left).unwrap().end = wf.right - 1;
-            ratings_else.get_mut(&wf.left).unwrap().start = wf.right;
-        } else {
-            ratings_ok.get_mut(&wf.left).unwrap().start = wf.right + 1;
-            ratings_else.get_mut(&wf.left).unwrap().end = wf.right;
-        }
+    /// Count the accepted combinations reaching `wf`'s rule at index `rule_idx` with `ratings`.
+    fn explore_workflow(
+        &self,
+        wf: &Workflow,
+        rule_idx: usize,
+        ratings: BTreeMap<String, Bounds>,
+    ) -> usize {
+        let Some(rule) = wf.rules.get(rule_idx) else {
+            return self.explore_part(&wf.fallback, ratings);
+        };
+        let (matched, rest) = rule.op.split(ratings[&rule.var], rule.value);
 
-        self.explore_ans(&wf.ok, ratings_ok);
-        self.explore_ans(&wf.orelse, ratings_else);
+        let mut total = 0;
+        if let Some(bounds) = matched {
+            let mut ratings_ok = ratings.clone();
+            ratings_ok.insert(rule.var.clone(), bounds);
+            total += self.explore_part(&rule.target, ratings_ok);
+        }
+        for bounds in rest {
+            let mut ratings_else = ratings.clone();
+            ratings_else.insert(rule.var.clone(), bounds);
+            total += self.explore_workflow(wf, rule_idx + 1, ratings_else);
+        }
+        total
     }
 
-    fn explore_ans(&self, ans: &Ans, ratings: HashMap<String, Range<usize>>) {
-        match ans {
-            Ans::Part(Part::Accepted) => {
-                *self.combinations_total.borrow_mut() += ratings
-                    .values()
-                    .map(|r| (r.end - r.start) + 1)
-                    .product::<usize>();
-            }
-            Ans::Part(Part::Rejected) => {}
-            Ans::Part(Part::Workflow(label)) => {
-                self.explore_workflow(&self.workflows.0[label], ratings);
-            }
-            Ans::Workflow(new_workflow) => {
-                self.explore_workflow(new_workflow, ratings);
+    fn explore_part(&self, part: &Part, ratings: BTreeMap<String, Bounds>) -> usize {
+        match part {
+            Part::Accepted => ratings.values().map(|b| b.len()).product(),
+            Part::Rejected => 0,
+            Part::Workflow(label) => {
+                self.explore_workflow(&self.workflows[label.as_str()], 0, ratings)
             }
         }
     }
@@ -80,112 +96,413 @@ impl FromStr for Puzzle {
         let workflows_raw = lines
             .by_ref()
             .take_while(|line| !line.is_empty())
-            .collect::<Vec<_>>()
-            .join("\n");
-        let workflows = Workflows::from_str(&workflows_raw)?;
+            .collect::<Vec<_>>();
+        let workflows = Workflows::from_str(&workflows_raw.join("\n"))?;
+        workflows.check_targets()?;
 
         let ratings_raw = lines.collect::<Vec<_>>().join("\n");
-        let ratings = Ratings::from_str(&ratings_raw)?;
+        let first_rating_line = workflows_raw.len() + 2;
+        let ratings = Ratings::parse(&ratings_raw, first_rating_line)?;
+
+        // Every category compared by a rule must be rated, other categories only add to the sum.
+        let used = workflows.categories();
+        for (i, rating) in ratings.0.iter().enumerate() {
+            if let Some(missing) = used.iter().find(|&&c| !rating.contains_key(c)) {
+                return Err(anyhow!(
+                    "line {}: rating is missing category '{missing}'",
+                    first_rating_line + i
+                ));
+            }
+        }
+
+        let categories = used
+            .into_iter()
+            .map(String::from)
+            .chain(ratings.0.iter().flat_map(|r| r.keys().cloned()))
+            .map(|c| (c, DEFAULT_BOUNDS))
+            .collect();
         Ok(Self {
             workflows,
             ratings,
-            combinations_total: RefCell::new(0),
+            categories,
         })
     }
 }
 
+const DEFAULT_BOUNDS: Bounds = Bounds { min: 1, max: 4000 };
+
+/// Bounds of a category written as `category=min..max`, e.g. `x=1..4000`.
+#[derive(Debug, Clone)]
+pub struct CategoryBounds {
+    pub category: String,
+    pub min: usize,
+    pub max: usize,
+}
+impl FromStr for CategoryBounds {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || anyhow!("expected 'category=min..max', got '{s}'");
+        let (category, range) = s.split_once('=').ok_or_else(err)?;
+        let (min, max) = range.split_once("..").ok_or_else(err)?;
+        Ok(Self {
+            category: category.to_string(),
+            min: min.parse().map_err(|_| err())?,
+            max: max.parse().map_err(|_| err())?,
+        })
+    }
+}
+
+/// Inclusive range of values a category can take while exploring the workflows.
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    min: usize,
+    max: usize,
+}
+impl Bounds {
+    fn new(min: usize, max: usize) -> Option<Self> {
+        (min <= max).then_some(Self { min, max })
+    }
+    fn len(&self) -> usize {
+        self.max - self.min + 1
+    }
+}
+
+/// Small cursor over a single line of input, used to report errors with their line and column.
+struct Cursor<'a> {
+    line: usize,
+    src: &'a str,
+    pos: usize,
+}
+impl<'a> Cursor<'a> {
+    fn new(src: &'a str, line: usize) -> Self {
+        Self { line, src, pos: 0 }
+    }
+
+    fn error(&self, msg: impl Display) -> Error {
+        anyhow!("line {}, column {}: {msg}", self.line, self.pos + 1)
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.src.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), Error> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(format!("expected '{c}'")))
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let rest = self.rest();
+        let len = rest.find(|c| !f(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn ident(&mut self) -> Result<&'a str, Error> {
+        let ident = self.take_while(|c| c.is_ascii_alphabetic());
+        if ident.is_empty() {
+            Err(self.error("expected an identifier"))
+        } else {
+            Ok(ident)
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, Error> {
+        let start = self.pos;
+        let digits = self.take_while(|c| c.is_ascii_digit());
+        digits.parse().map_err(|e| {
+            self.pos = start;
+            self.error(format!("expected a number ({e})"))
+        })
+    }
+
+    fn end(&self) -> Result<(), Error> {
+        if self.is_done() {
+            Ok(())
+        } else {
+            Err(self.error("unexpected trailing characters"))
+        }
+    }
+}
+
 #[derive(Debug)]
-struct Workflows(HashMap<String, Workflow>);
+struct Workflows {
+    workflows: Vec<Workflow>,
+    index: HashMap<String, usize>,
+}
+impl Workflows {
+    /// Names of the categories compared by at least one rule.
+    fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<_> = self
+            .workflows
+            .iter()
+            .flat_map(|wf| wf.rules.iter().map(|rule| rule.var.as_str()))
+            .collect();
+        categories.sort_unstable();
+        categories.dedup();
+        categories
+    }
+
+    fn get(&self, label: &str) -> Option<&Workflow> {
+        self.index.get(label).map(|&i| &self.workflows[i])
+    }
+
+    /// Fail if there is no `in` workflow or if a rule sends parts to an undefined workflow, workflows being
+    /// numbered from line 1.
+    fn check_targets(&self) -> Result<(), Error> {
+        if self.get("in").is_none() {
+            return Err(anyhow!("no 'in' workflow"));
+        }
+        for (i, wf) in self.workflows.iter().enumerate() {
+            let targets = wf
+                .rules
+                .iter()
+                .map(|rule| (&rule.target, rule.target_column))
+                .chain([(&wf.fallback, wf.fallback_column)]);
+            for (target, column) in targets {
+                if let Part::Workflow(label) = target {
+                    if self.get(label).is_none() {
+                        return Err(anyhow!(
+                            "line {}, column {column}: unknown workflow '{label}'",
+                            i + 1
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+impl Index<&str> for Workflows {
+    type Output = Workflow;
+    fn index(&self, label: &str) -> &Self::Output {
+        self.get(label)
+            .unwrap_or_else(|| panic!("unknown workflow '{label}'"))
+    }
+}
 impl FromStr for Workflows {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut inner = HashMap::new();
-        for line in s.lines() {
-            let line_split = line.split_once('{').ok_or(anyhow!(""))?;
-            let label = line_split.0.to_string();
-            let workflow = Workflow::from_str(&line_split.1.replace('}', ""))?;
-            inner.insert(label, workflow);
+        let mut workflows = Vec::new();
+        let mut index = HashMap::new();
+        for (i, line) in s.lines().enumerate() {
+            let mut cursor = Cursor::new(line, i + 1);
+            let label = cursor.ident()?.to_string();
+            cursor.expect('{')?;
+            let workflow = Workflow::parse(&mut cursor, label)?;
+            cursor.expect('}')?;
+            cursor.end()?;
+            if index
+                .insert(workflow.label.clone(), workflows.len())
+                .is_some()
+            {
+                return Err(anyhow!(
+                    "line {}, column 1: duplicate workflow '{}'",
+                    i + 1,
+                    workflow.label
+                ));
+            }
+            workflows.push(workflow);
         }
-        Ok(Self(inner))
+        Ok(Self { workflows, index })
+    }
+}
+impl Display for Workflows {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for wf in self.workflows.iter() {
+            writeln!(f, "{}{{{wf}}}", wf.label)?;
+        }
+        Ok(())
     }
 }
 
+/// A workflow such as `px{a<2006:qkq,m>2090:A,rfg}`: rules tried in order, then a fallback.
 #[derive(Debug)]
 struct Workflow {
-    left: String,
-    cmp: Ordering,
-    right: usize,
-    ok: Box<Ans>,
-    orelse: Box<Ans>,
+    label: String,
+    rules: Vec<Rule>,
+    fallback: Part,
+    /// Column of the fallback, for error messages
+    fallback_column: usize,
 }
 impl Workflow {
     fn process(&self, rating: &HashMap<String, usize>) -> Part {
-        if rating[&self.left].cmp(&self.right) == self.cmp {
-            Self::ans_process(&self.ok, rating)
-        } else {
-            Self::ans_process(&self.orelse, rating)
-        }
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(rating))
+            .map(|rule| &rule.target)
+            .unwrap_or(&self.fallback)
+            .clone()
     }
-    fn ans_process(ans: &Ans, rating: &HashMap<String, usize>) -> Part {
-        match ans {
-            Ans::Part(part) => part.clone(),
-            Ans::Workflow(new_workflow) => Self::process(new_workflow, rating),
+
+    /// Parse the body of a workflow, i.e. everything between the braces.
+    fn parse(cursor: &mut Cursor, label: String) -> Result<Self, Error> {
+        let mut rules = Vec::new();
+        loop {
+            let start = cursor.pos;
+            let ident = cursor.ident()?;
+            if cursor.peek().is_some_and(|c| ['<', '>', '='].contains(&c)) {
+                cursor.pos = start;
+                rules.push(Rule::parse(cursor)?);
+                cursor.expect(',')?;
+            } else {
+                let fallback = Part::from_str(ident)?;
+                return Ok(Self {
+                    label,
+                    rules,
+                    fallback,
+                    fallback_column: start + 1,
+                });
+            }
         }
     }
 }
 impl FromStr for Workflow {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let ss = s.split_once(':').ok_or(anyhow!(""))?;
-        let cond = ss.0;
-        let cmp = if cond.contains('<') {
-            Ordering::Less
-        } else {
-            Ordering::Greater
-        };
-        let mut cond_split = cond.split(['<', '>']);
-        let left = cond_split.next().ok_or(anyhow!(""))?.to_string();
-        let right = cond_split.next().ok_or(anyhow!(""))?.parse()?;
-        let rest_split = ss.1.split_once(',').ok_or(anyhow!(""))?;
-        let ok = Box::new(if let Ok(work) = Self::from_str(rest_split.0) {
-            Ans::Workflow(work)
-        } else {
-            Ans::Part(Part::from_str(rest_split.0)?)
-        });
-        let orelse = Box::new(if let Ok(work) = Self::from_str(rest_split.1) {
-            Ans::Workflow(work)
-        } else {
-            Ans::Part(Part::from_str(rest_split.1)?)
-        });
+        let mut cursor = Cursor::new(s, 1);
+        let workflow = Self::parse(&mut cursor, String::new())?;
+        cursor.end()?;
+        Ok(workflow)
+    }
+}
+impl Display for Workflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rule in self.rules.iter() {
+            write!(f, "{rule},")?;
+        }
+        write!(f, "{}", self.fallback)
+    }
+}
+
+/// A single conditional step of a workflow, e.g. `a<2006:qkq`.
+#[derive(Debug)]
+struct Rule {
+    var: String,
+    op: Op,
+    value: usize,
+    target: Part,
+    /// Column of the target, for error messages
+    target_column: usize,
+}
+impl Rule {
+    fn matches(&self, rating: &HashMap<String, usize>) -> bool {
+        self.op.eval(rating[&self.var], self.value)
+    }
 
+    fn parse(cursor: &mut Cursor) -> Result<Self, Error> {
+        let var = cursor.ident()?.to_string();
+        let op = Op::parse(cursor)?;
+        let value = cursor.number()?;
+        cursor.expect(':')?;
+        let target_column = cursor.pos + 1;
+        let target = Part::from_str(cursor.ident()?)?;
         Ok(Self {
-            left,
-            cmp,
-            right,
-            ok,
-            orelse,
+            var,
+            op,
+            value,
+            target,
+            target_column,
         })
     }
 }
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}:{}", self.var, self.op, self.value, self.target)
+    }
+}
 
-#[derive(Debug)]
-enum Variable {
-    VarX,
-    VarM,
-    VarA,
-    VarS,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
 }
-impl FromStr for Variable {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "x" => Ok(Variable::VarX),
-            "m" => Ok(Variable::VarM),
-            "a" => Ok(Variable::VarA),
-            "s" => Ok(Variable::VarS),
-            _ => Err(anyhow!("failed to convert variable")),
+impl Op {
+    fn eval(&self, left: usize, right: usize) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => left == right,
         }
     }
+
+    /// Split `bounds` into the values satisfying `x <op> value` and the ones that don't.
+    fn split(&self, bounds: Bounds, value: usize) -> (Option<Bounds>, Vec<Bounds>) {
+        let Bounds { min, max } = bounds;
+        let up_to = |end: Option<usize>| end.and_then(|end| Bounds::new(min, end.min(max)));
+        let from = |start: usize| Bounds::new(start.max(min), max);
+        let (matched, rest) = match self {
+            Op::Lt => (up_to(value.checked_sub(1)), from(value)),
+            Op::Le => (up_to(Some(value)), from(value + 1)),
+            Op::Gt => (from(value + 1), up_to(Some(value))),
+            Op::Ge => (from(value), up_to(value.checked_sub(1))),
+            Op::Eq => {
+                let rest = [up_to(value.checked_sub(1)), from(value + 1)];
+                let matched = Bounds::new(value.max(min), value.min(max));
+                return (matched, rest.into_iter().flatten().collect());
+            }
+        };
+        (matched, rest.into_iter().collect())
+    }
+
+    fn parse(cursor: &mut Cursor) -> Result<Self, Error> {
+        let op = if cursor.eat('<') {
+            if cursor.eat('=') {
+                Op::Le
+            } else {
+                Op::Lt
+            }
+        } else if cursor.eat('>') {
+            if cursor.eat('=') {
+                Op::Ge
+            } else {
+                Op::Gt
+            }
+        } else if cursor.eat('=') {
+            cursor.expect('=')?;
+            Op::Eq
+        } else {
+            return Err(cursor.error("expected one of '<', '<=', '>', '>=', '=='"));
+        };
+        Ok(op)
+    }
+}
+impl Display for Op {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+            Op::Eq => "==",
+        };
+        write!(f, "{s}")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -206,60 +523,152 @@ impl FromStr for Part {
         }
     }
 }
-
-#[derive(Debug)]
-enum Ans {
-    Part(Part),
-    Workflow(Workflow),
+impl Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Part::Accepted => write!(f, "A"),
+            Part::Rejected => write!(f, "R"),
+            Part::Workflow(label) => write!(f, "{label}"),
+        }
+    }
 }
 
 #[derive(Debug)]
 struct Ratings(Vec<Rating>);
-impl FromStr for Ratings {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Ratings {
+    /// Parse the ratings block, numbering lines from `first_line` in error messages.
+    fn parse(s: &str, first_line: usize) -> Result<Self, Error> {
         let v = s
             .lines()
-            .map(|line| {
-                let line = line.replace(['{', '}'], "");
-                line.split(',')
-                    .map(|r| {
-                        let rr = r.split_once('=').unwrap();
-                        let key = rr.0.to_string(); // x,m,a,s
-                        let value = rr.1.parse().unwrap();
-                        (key, value)
-                    })
-                    .collect()
+            .enumerate()
+            .map(|(i, line)| {
+                let mut cursor = Cursor::new(line, first_line + i);
+                let mut rating = Rating::new();
+                cursor.expect('{')?;
+                loop {
+                    let key = cursor.ident()?.to_string();
+                    cursor.expect('=')?;
+                    let value = cursor.number()?;
+                    rating.insert(key, value);
+                    if !cursor.eat(',') {
+                        break;
+                    }
+                }
+                cursor.expect('}')?;
+                cursor.end()?;
+                Ok(rating)
             })
-            .collect();
+            .collect::<Result<_, Error>>()?;
 
         Ok(Self(v))
     }
 }
+impl FromStr for Ratings {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, 1)
+    }
+}
 type Rating = HashMap<String, usize>;
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const EXAMPLE: &str = "px{a<2006:qkq,m>2090:A,rfg}\npv{a>1716:R,A}\nlnx{m>1548:A,A}\nrfg{s<537:gd,x>2440:R,A}\nqs{s>3448:A,lnx}\nqkq{x<1416:A,crn}\ncrn{x>2662:A,R}\nin{s<1351:px,qqz}\nqqz{s>2770:qs,m<1801:hdj,R}\ngd{a>3333:R,R}\nhdj{m>838:A,pv}\n\n{x=787,m=2655,a=1222,s=2876}\n{x=1679,m=44,a=2067,s=496}\n{x=2036,m=264,a=79,s=2244}\n{x=2461,m=1339,a=466,s=291}\n{x=2127,m=1623,a=2188,s=1013}";
+
     #[test]
     fn test_parse_workflow() {
         let s = "a<2006:qkq,m>2090:A,rfg";
         let workflow = Workflow::from_str(s).unwrap();
-        println!("{workflow:?}");
+        assert_eq!(workflow.rules.len(), 2);
+        let rule = &workflow.rules[0];
+        assert_eq!(
+            (rule.var.as_str(), rule.op, rule.value),
+            ("a", Op::Lt, 2006)
+        );
+        assert_eq!(workflow.fallback.to_string(), "rfg");
+        assert_eq!(workflow.to_string(), s);
     }
 
     #[test]
     fn test_parse_workflows() {
         let s = "px{a<2006:qkq,m>2090:A,rfg}\npv{a>1716:R,A}\nlnx{m>1548:A,A}\nrfg{s<537:gd,x>2440:R,A}\nqs{s>3448:A,lnx}\nqkq{x<1416:A,crn}\ncrn{x>2662:A,R}\nin{s<1351:px,qqz}\nqqz{s>2770:qs,m<1801:hdj,R}\ngd{a>3333:R,R}\nhdj{m>838:A,pv}";
         let workflows = Workflows::from_str(s).unwrap();
-        println!("{:?}", workflows.0["crn"]);
+        assert_eq!(workflows["crn"].to_string(), "x>2662:A,R");
+        assert_eq!(workflows.to_string().trim_end(), s);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Workflows::from_str("px{a<2006:qkq,A}\npv{a~1716:R,A}").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 5: expected '}'");
+        let err = Workflows::from_str("px{a<2006:qkq,A}\npv{a=1716:R,A}").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 6: expected '='");
+        let err = Puzzle::from_str("px{a<1:A,R}\n\n{a=1}").unwrap_err();
+        assert_eq!(err.to_string(), "no 'in' workflow");
+        let err = Puzzle::from_str("in{a<1:px,R}\npx{a<1:zz,R}\n\n{a=1}").unwrap_err();
+        assert_eq!(err.to_string(), "line 2, column 8: unknown workflow 'zz'");
+        let err = Puzzle::from_str("in{a<1:A,px}\n\n{a=1}").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 10: unknown workflow 'px'");
+        let err = Puzzle::from_str("in{q<10:A,R}\n\n{x=1}").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: rating is missing category 'q'");
+        let err = Puzzle::from_str("in{x<10:A,R}\n\n{x=1,m=}").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 3, column 8: expected a number (cannot parse integer from empty string)"
+        );
+    }
+
+    #[test]
+    fn test_extended_comparisons() {
+        let s = "a<=2006:qkq,m>=2090:A,x==5:R,rfg";
+        let workflow = Workflow::from_str(s).unwrap();
+        assert_eq!(workflow.to_string(), s);
+
+        let puzzle =
+            Puzzle::from_str("in{x==1:A,x<=2:R,x>=4000:A,R}\n\n{x=1,m=1,a=1,s=1}").unwrap();
+        assert_eq!(puzzle.run(), 4);
+        assert_eq!(puzzle.explore(), 2 * 4000 * 4000 * 4000);
+    }
+
+    #[test]
+    fn test_custom_categories() {
+        let s = "in{speed>10:A,weight<5:R,A}\n\n{speed=11,weight=3}\n{speed=2,weight=3,colour=7}";
+        let puzzle = Puzzle::from_str(s).unwrap();
+        assert_eq!(puzzle.run(), 14);
+        let puzzle = puzzle
+            .with_bounds("speed", 1, 20)
+            .unwrap()
+            .with_bounds("weight", 1, 10)
+            .unwrap()
+            .with_bounds("colour", 1, 1)
+            .unwrap();
+        assert_eq!(puzzle.explore(), 10 * 10 + 10 * 6);
+
+        let bounds = CategoryBounds::from_str("speed=1..20").unwrap();
+        assert_eq!(
+            (bounds.category.as_str(), bounds.min, bounds.max),
+            ("speed", 1, 20)
+        );
+        assert!(CategoryBounds::from_str("speed=1-20").is_err());
+        let puzzle = Puzzle::from_str(s).unwrap();
+        assert!(puzzle.with_bounds("size", 1, 2).is_err());
+    }
+
+    #[test]
+    fn test_example() {
+        let puzzle = Puzzle::from_str(EXAMPLE).unwrap();
+        assert_eq!(puzzle.run(), 19114);
+        assert_eq!(puzzle.explore(), 167409079868000);
     }
 
     #[test]
     fn test_ratings() {
         let s = "{x=787,m=2655,a=1222,s=2876}\n{x=1679,m=44,a=2067,s=496}\n{x=2036,m=264,a=79,s=2244}\n{x=2461,m=1339,a=466,s=291}\n{x=2127,m=1623,a=2188,s=1013}";
         let ratings = Ratings::from_str(s).unwrap();
-        println!("{:?}", ratings);
+        assert_eq!(ratings.0.len(), 5);
+        assert_eq!(ratings.0[1]["m"], 44);
+        assert_eq!(ratings.0[4].values().sum::<usize>(), 6951);
     }
 }
@@ -1,23 +1,37 @@
 use std::str::FromStr;
 
-use common::Puzzle;
+use common::{CategoryBounds, Puzzle};
+use util::clap::{self, Args};
 
 mod common;
 
+#[derive(Args)]
+struct Options {
+    /// Values a category can take in part 2, as `category=min..max` (1..4000 by default)
+    #[arg(long)]
+    bounds: Vec<CategoryBounds>,
+}
+
 fn main() {
-    util::run!();
+    util::run!(Options);
 }
 
-fn part1(file: &str) -> String {
+fn part1(file: &str, _: &Options) -> String {
     let data = std::fs::read_to_string(file).unwrap();
     let puzzle = Puzzle::from_str(&data).unwrap();
     let ans = puzzle.run();
 
     ans.to_string()
 }
-fn part2(file: &str) -> String {
+fn part2(file: &str, options: &Options) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let puzzle = Puzzle::from_str(&data).unwrap();
+    let puzzle = options
+        .bounds
+        .iter()
+        .try_fold(Puzzle::from_str(&data).unwrap(), |puzzle, b| {
+            puzzle.with_bounds(&b.category, b.min, b.max)
+        })
+        .unwrap();
     let ans = puzzle.explore();
     ans.to_string()
 }
@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Error};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    str::FromStr,
+};
+
+/// Number of cubes of each colour, for a draw or for the content of the bag
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Pick(BTreeMap<String, usize>);
+
+impl Pick {
+    fn add(&mut self, nb: usize, color: &str) {
+        *self.0.entry(color.to_string()).or_default() += nb;
+    }
+
+    fn get(&self, color: &str) -> usize {
+        self.0.get(color).copied().unwrap_or(0)
+    }
+
+    /// Fail if `bag` doesn't know one of the colours of the draw
+    fn check_colors(&self, bag: &Pick) -> Result<(), Error> {
+        match self.0.keys().find(|color| !bag.0.contains_key(*color)) {
+            Some(color) => Err(anyhow!("color '{color}' is not in the bag")),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether the draw could come out of `bag`, which must know all the colours of the draw
+    pub fn is_possible(&self, bag: &Pick) -> Result<bool, Error> {
+        self.check_colors(bag)?;
+        Ok(self.0.iter().all(|(color, &nb)| nb <= bag.get(color)))
+    }
+
+    pub fn power(&self) -> usize {
+        self.0.values().product()
+    }
+}
+
+impl FromStr for Pick {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pick = Pick::default();
+
+        // X blue, Y red, Z green
+        for item in s.split(',') {
+            let mut item = item.split_whitespace();
+            let nb = item.next().ok_or(anyhow!("failed to parse nb"))?;
+            let nb: usize = nb
+                .parse()
+                .map_err(|_| anyhow!("invalid number of cubes '{nb}'"))?;
+            let color = item.next().ok_or(anyhow!("failed to parse color"))?;
+            pick.add(nb, color);
+        }
+
+        Ok(pick)
+    }
+}
+
+impl Display for Pick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<String> = self
+            .0
+            .iter()
+            .map(|(color, nb)| format!("{nb} {color}"))
+            .collect();
+        write!(f, "{}", items.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub struct Game {
+    id: usize,
+    picks: Vec<Pick>,
+}
+
+impl Game {
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    pub fn is_possible(&self, bag: &Pick) -> Result<bool, Error> {
+        Ok(self.violation(bag)?.is_none())
+    }
+
+    /// First draw that could not come out of `bag`, with its index in the game. Every draw is checked against
+    /// the colours of the bag first.
+    pub fn violation(&self, bag: &Pick) -> Result<Option<(usize, &Pick)>, Error> {
+        for pick in self.picks.iter() {
+            pick.check_colors(bag)?;
+        }
+        for (i, pick) in self.picks.iter().enumerate() {
+            if !pick.is_possible(bag)? {
+                return Ok(Some((i, pick)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Smallest bag the game could be played with, holding every colour of `colors`
+    pub fn min_possible_set(&self, colors: &BTreeSet<String>) -> Pick {
+        Pick(
+            colors
+                .iter()
+                .map(|color| {
+                    let nb = self.picks.iter().map(|pick| pick.get(color)).max();
+                    (color.clone(), nb.unwrap_or(0))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl FromStr for Game {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Format is as follow
+        // Game N: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        let (game_str, cubes_str) = s
+            .split_once(':')
+            .ok_or(anyhow!("missing ':' after the game id"))?;
+
+        // First process game id ==> Game N
+        let game_id: usize = game_str
+            .strip_prefix("Game ")
+            .and_then(|c| c.trim().parse().ok())
+            .ok_or(anyhow!("failed to parse game id in '{game_str}'"))?;
+
+        // Process the cubes ==> 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+        let picks = cubes_str
+            .split(';')
+            .enumerate()
+            .map(|(i, c)| {
+                Pick::from_str(c).map_err(|e| anyhow!("game {game_id}, draw {}: {e}", i + 1))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { id: game_id, picks })
+    }
+}
+
+#[derive(Debug)]
+pub struct Games {
+    games: Vec<Game>,
+    /// Every colour drawn in the games
+    colors: BTreeSet<String>,
+}
+
+impl FromStr for Games {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let games = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| Game::from_str(line).map_err(|e| anyhow!("line {}: {e}", i + 1)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let colors = games
+            .iter()
+            .flat_map(|game| game.picks.iter())
+            .flat_map(|pick| pick.0.keys().cloned())
+            .collect();
+        Ok(Self { games, colors })
+    }
+}
+
+impl Games {
+    pub fn games(&self) -> &[Game] {
+        &self.games
+    }
+
+    /// Every colour drawn in the games
+    pub fn colors(&self) -> &BTreeSet<String> {
+        &self.colors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+
+    #[test]
+    fn test_example() {
+        let games = Games::from_str(EXAMPLE).unwrap();
+        let bag = Pick::from_str("12 red, 13 green, 14 blue").unwrap();
+        let possible: usize = games
+            .games()
+            .iter()
+            .filter(|game| game.is_possible(&bag).unwrap())
+            .map(Game::id)
+            .sum();
+        assert_eq!(possible, 8);
+        let powers: usize = games
+            .games()
+            .iter()
+            .map(|game| game.min_possible_set(games.colors()).power())
+            .sum();
+        assert_eq!(powers, 2286);
+
+        let game = &games.games()[2];
+        let (i, pick) = game.violation(&bag).unwrap().unwrap();
+        assert_eq!(
+            (i, pick.to_string().as_str()),
+            (0, "6 blue, 8 green, 20 red")
+        );
+        let err = game
+            .is_possible(&Pick::from_str("12 red, 13 green").unwrap())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "color 'blue' is not in the bag");
+        // The unknown colour comes after a draw which doesn't fit the bag
+        let game = Game::from_str("Game 1: 20 red; 1 yellow").unwrap();
+        let err = game.is_possible(&bag).unwrap_err();
+        assert_eq!(err.to_string(), "color 'yellow' is not in the bag");
+        let err = Pick::from_str("20 red, 1 yellow")
+            .unwrap()
+            .is_possible(&bag)
+            .unwrap_err();
+        assert_eq!(err.to_string(), "color 'yellow' is not in the bag");
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        let err = Games::from_str("Game 1: 3 blue\nGame 2 3 blue").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: missing ':' after the game id");
+        let err = Game::from_str("Game 7: 3 blue; 1 red, -2 green").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "game 7, draw 2: invalid number of cubes '-2'"
+        );
+        let err = Game::from_str("Game 7: 3 blue; 1 red; 2").unwrap_err();
+        assert_eq!(err.to_string(), "game 7, draw 3: failed to parse color");
+        let err = Game::from_str("Round 7: 3 blue").unwrap_err();
+        assert_eq!(err.to_string(), "failed to parse game id in 'Round 7'");
+    }
+}
@@ -1,129 +1,62 @@
-use anyhow::{anyhow, Error};
+use common::{Games, Pick};
 use std::str::FromStr;
+use util::clap::{self, Args};
 
-#[derive(Default, Debug)]
-struct Pick {
-    red: usize,
-    green: usize,
-    blue: usize,
-}
+mod common;
 
-impl Pick {
-    fn add(&mut self, nb: usize, color: &str) {
-        match color {
-            "red" => self.red += nb,
-            "green" => self.green += nb,
-            "blue" => self.blue += nb,
-            _ => (),
-        }
-    }
-    pub fn is_possible(&self) -> bool {
-        // only 12 red cubes, 13 green cubes, and 14 blue cubes are possible
-        self.red <= 12 && self.green <= 13 && self.blue <= 14
-    }
-
-    pub fn power(&self) -> usize {
-        self.red * self.green * self.blue
-    }
+#[derive(Args)]
+struct Options {
+    /// Content of the bag the games are checked against
+    #[arg(long, default_value = "12 red, 13 green, 14 blue")]
+    bag: Pick,
+    /// Print the minimum bag, its power and the first draw not fitting the bag of every game, not the answers
+    #[arg(long)]
+    stats: bool,
 }
 
-impl FromStr for Pick {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut pick = Pick::default();
-
-        // X blue, Y red, Z green
-        for item in s.split(',') {
-            let item = item
-                .strip_prefix(' ')
-                .ok_or(anyhow!("failed to strip whitespace"))?;
-            let mut item = item.split_whitespace();
-            let nb: usize = item.next().ok_or(anyhow!("failed to parse nb"))?.parse()?;
-            let color = item.next().ok_or(anyhow!("failed to parse color"))?;
-            pick.add(nb, color);
+fn main() {
+    let cli = util::Cli::<Options>::with_extras();
+    if cli.extras.stats {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let games = Games::from_str(&data).unwrap();
+        for game in games.games().iter() {
+            let min_bag = game.min_possible_set(games.colors());
+            let violation = match game.violation(&cli.extras.bag).unwrap() {
+                Some((i, pick)) => format!("draw {} ({pick})", i + 1),
+                None => "none".to_string(),
+            };
+            println!(
+                "game={} min_bag=({min_bag}) power={} violation={violation}",
+                game.id(),
+                min_bag.power()
+            );
         }
-
-        Ok(pick)
-    }
-}
-
-#[derive(Debug)]
-struct Game {
-    id: usize,
-    picks: Vec<Pick>,
-}
-
-impl Game {
-    fn is_possible(&self) -> bool {
-        self.picks.iter().all(|pick| pick.is_possible())
-    }
-
-    fn min_possible_set(&self) -> Pick {
-        let red = self.picks.iter().max_by_key(|k| k.red).unwrap().red;
-        let green = self.picks.iter().max_by_key(|k| k.green).unwrap().green;
-        let blue = self.picks.iter().max_by_key(|k| k.blue).unwrap().blue;
-        Pick { red, green, blue }
+        return;
     }
+    println!("part1={}", part1(&cli.file, &cli.extras));
+    println!("part2={}", part2(&cli.file, &cli.extras));
 }
 
-impl FromStr for Game {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Format is as follow
-        // Game N: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-        let s: Vec<&str> = s.split(':').collect();
-
-        // First process game id ==> Game N
-        let game_str = s.first().ok_or(anyhow!("failed to get game data"))?;
-        let game_id: usize = game_str
-            .strip_prefix("Game ")
-            .map(|c| c.parse().unwrap())
-            .ok_or(anyhow!("failed to parse game id"))?;
-
-        // Process the cubes ==> 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
-        let cubes_str = s.get(1).ok_or(anyhow!("failed to get cubes data"))?;
-        let picks = cubes_str
-            .split(';')
-            .map(|c| Pick::from_str(c).unwrap())
-            .collect();
-
-        Ok(Self { id: game_id, picks })
-    }
-}
-
-fn main() {
-    let cli = util::Cli::get();
-    println!("part1={}", part1(&cli.file));
-    println!("part2={}", part2(&cli.file));
-}
-
-fn part1(file: &str) -> String {
+fn part1(file: &str, options: &Options) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let ans: usize = data
-        .lines()
-        .filter_map(|line| {
-            let game = Game::from_str(line).unwrap();
-
-            if game.is_possible() {
-                Some(game.id)
-            } else {
-                None
-            }
-        })
+    let games = Games::from_str(&data).unwrap();
+    let ans: usize = games
+        .games()
+        .iter()
+        .filter(|game| game.is_possible(&options.bag).unwrap())
+        .map(|game| game.id())
         .sum();
 
     ans.to_string()
 }
 
-fn part2(file: &str) -> String {
+fn part2(file: &str, _: &Options) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let ans: usize = data
-        .lines()
-        .map(|line| {
-            let game = Game::from_str(line).unwrap();
-            let set = game.min_possible_set();
-            set.power()
-        })
+    let games = Games::from_str(&data).unwrap();
+    let ans: usize = games
+        .games()
+        .iter()
+        .map(|game| game.min_possible_set(games.colors()).power())
         .sum();
 
     ans.to_string()
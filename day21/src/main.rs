@@ -15,7 +15,7 @@ fn part1(file: &str) -> String {
 }
 fn part2(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let puzzle = Puzzle::from_str(&data).unwrap();
+    let _puzzle = Puzzle::from_str(&data).unwrap();
     let ans = 0;
     ans.to_string()
 }
@@ -1,109 +1,182 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, Error};
 use util::grid::Grid;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub enum Symbol {
-    Number(u32),
+pub enum Cell {
     Period,
-    Other(char),
+    /// Digit of the number with this id
+    Number(usize),
+    Symbol(char),
 }
 
-impl From<char> for Symbol {
-    fn from(value: char) -> Self {
-        match value {
-            '.' => Self::Period,
-            c if c.is_numeric() => Self::Number(c.to_digit(10).unwrap()),
-            _ => Self::Other(value),
-        }
-    }
+/// A number of the schematic, written on `row` from column `start` to column `end` (excluded)
+#[derive(Debug, PartialEq)]
+pub struct Span {
+    pub value: u32,
+    pub row: usize,
+    pub start: usize,
+    pub end: usize,
 }
 
-pub type Number = String;
-
-pub fn make_symbol_grid(data: &str, numbers: &[Vec<String>]) -> Grid<Symbol> {
-    let mut grid = Grid::new(
-        data.lines()
-            .map(|line| line.chars().map(Symbol::from).collect())
-            .collect(),
-    );
-
-    for (y, _) in numbers.iter().enumerate() {
-        let ns = &numbers[y];
-        let mut ni = 0;
-        let mut rep = 0;
-        for x in 0..grid.row_len(y).unwrap() {
-            if let Some(Symbol::Number(_)) = grid.get((x, y)) {
-                let n: u32 = ns[ni].parse().unwrap();
-                grid.set((x, y), Symbol::Number(n));
-                rep += 1;
-                if rep >= ns[ni].len() {
-                    rep = 0;
-                    ni += 1;
-                }
+/// The engine schematic. Numbers are spans identified by their index in `spans`, and any character that is
+/// neither a digit nor a period is a symbol.
+#[derive(Debug)]
+pub struct Schematic {
+    grid: Grid<Cell>,
+    spans: Vec<Span>,
+    /// Position of every symbol, in reading order
+    symbols: Vec<(char, (usize, usize))>,
+}
+impl FromStr for Schematic {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spans: Vec<Span> = vec![];
+        let mut symbols = vec![];
+        let mut rows = vec![];
+        for (y, line) in s.lines().enumerate() {
+            let mut row = vec![];
+            for (x, c) in line.chars().enumerate() {
+                let cell = match c {
+                    '.' => Cell::Period,
+                    '0'..='9' => {
+                        let digit = c.to_digit(10).unwrap();
+                        match spans.last_mut() {
+                            Some(span) if span.row == y && span.end == x => {
+                                span.value = span
+                                    .value
+                                    .checked_mul(10)
+                                    .and_then(|v| v.checked_add(digit))
+                                    .ok_or(anyhow!("line {}: number too large", y + 1))?;
+                                span.end += 1;
+                            }
+                            _ => spans.push(Span {
+                                value: digit,
+                                row: y,
+                                start: x,
+                                end: x + 1,
+                            }),
+                        }
+                        Cell::Number(spans.len() - 1)
+                    }
+                    c => {
+                        symbols.push((c, (x, y)));
+                        Cell::Symbol(c)
+                    }
+                };
+                row.push(cell);
             }
+            rows.push(row);
         }
-    }
-    grid
-}
-
-pub fn extract_numbers_matrix(data: &str) -> Vec<Vec<Number>> {
-    data.lines()
-        .map(|line| {
-            let s = line.split(['#', '$', '%', '&', '*', '+', '-', '.', '/', '=', '@']);
-            let v: Vec<String> = s
-                .map(|w| w.chars().filter(|c| c.is_numeric()).collect::<String>())
-                .filter(|w| !w.is_empty())
-                .collect();
-            v
+        Ok(Self {
+            grid: Grid::new(rows),
+            spans,
+            symbols,
         })
-        .collect()
-}
-
-#[allow(unused)]
-pub fn display_separators(s: &str) {
-    let mut seps: Vec<char> = s
-        .lines()
-        .flat_map(|line| line.chars().filter(|c| !c.is_numeric()).collect::<Vec<_>>())
-        .collect();
-    seps.sort();
-    seps.dedup();
-    println!("seps={seps:?}");
-}
-
-pub fn adjacent_numbers((x, y): (usize, usize), grid: &Grid<Symbol>) -> Vec<u32> {
-    let mut adjacents = vec![];
-    // up-left
-    if let Some(Symbol::Number(n)) = grid.get((x - 1, y - 1)) {
-        adjacents.push(*n);
     }
-    //up
-    if let Some(Symbol::Number(n)) = grid.get((x, y - 1)) {
-        adjacents.push(*n);
+}
+impl Schematic {
+    pub fn span(&self, id: usize) -> &Span {
+        &self.spans[id]
     }
-    //up-right
-    if let Some(Symbol::Number(n)) = grid.get((x + 1, y - 1)) {
-        adjacents.push(*n);
+
+    /// Ids of the distinct numbers touching `(x, y)`, diagonals included
+    pub fn adjacent_spans(&self, (x, y): (usize, usize)) -> Vec<usize> {
+        let mut ids: Vec<usize> = (y.saturating_sub(1)..=y + 1)
+            .flat_map(|ny| (x.saturating_sub(1)..=x + 1).map(move |nx| (nx, ny)))
+            .filter(|&pos| pos != (x, y))
+            .filter_map(|pos| match self.grid.get(pos) {
+                Some(Cell::Number(id)) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
     }
-    //right
-    if let Some(Symbol::Number(n)) = grid.get((x + 1, y)) {
-        adjacents.push(*n);
+
+    /// Every occurrence of `symbol`, with the ids of the numbers touching it
+    pub fn parts_adjacent_to(&self, symbol: char) -> Vec<((usize, usize), Vec<usize>)> {
+        self.symbols
+            .iter()
+            .filter(|(c, _)| *c == symbol)
+            .map(|&(_, pos)| (pos, self.adjacent_spans(pos)))
+            .collect()
     }
-    //down-right
-    if let Some(Symbol::Number(n)) = grid.get((x + 1, y + 1)) {
-        adjacents.push(*n);
+
+    /// Ids of the part numbers, the numbers touching at least one symbol
+    pub fn parts(&self) -> Vec<usize> {
+        let mut ids: Vec<usize> = self
+            .symbols
+            .iter()
+            .flat_map(|&(_, pos)| self.adjacent_spans(pos))
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
     }
-    //down
-    if let Some(Symbol::Number(n)) = grid.get((x, y + 1)) {
-        adjacents.push(*n);
+
+    pub fn part_numbers_sum(&self) -> u32 {
+        self.parts()
+            .into_iter()
+            .map(|id| self.spans[id].value)
+            .sum()
     }
-    //down-left
-    if let Some(Symbol::Number(n)) = grid.get((x - 1, y + 1)) {
-        adjacents.push(*n);
+
+    /// Products of the two numbers touching the `*` symbols touching exactly two numbers
+    pub fn gear_ratios(&self) -> Vec<u32> {
+        self.parts_adjacent_to('*')
+            .into_iter()
+            .filter(|(_, ids)| ids.len() == 2)
+            .map(|(_, ids)| ids.iter().map(|&id| self.spans[id].value).product())
+            .collect()
     }
-    //left
-    if let Some(Symbol::Number(n)) = grid.get((x - 1, y)) {
-        adjacents.push(*n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+
+    #[test]
+    fn test_example() {
+        let schematic = Schematic::from_str(EXAMPLE).unwrap();
+        assert_eq!(schematic.part_numbers_sum(), 4361);
+        assert_eq!(schematic.gear_ratios().iter().sum::<u32>(), 467835);
+        let (pos, ids) = &schematic.parts_adjacent_to('#')[0];
+        assert_eq!(*pos, (6, 3));
+        assert_eq!(schematic.span(ids[0]).value, 633);
     }
 
-    adjacents.dedup();
-    adjacents
+    #[test]
+    fn test_equal_numbers_and_edges() {
+        // Two distinct numbers with the same value, and symbols on the edges of the grid
+        let schematic = Schematic::from_str("2.2\n.*.\n%..").unwrap();
+        assert_eq!(schematic.gear_ratios(), [4]);
+        assert_eq!(schematic.parts(), [0, 1]);
+
+        let schematic = Schematic::from_str("*12\n...\n..~").unwrap();
+        assert_eq!(
+            schematic.span(0),
+            &Span {
+                value: 12,
+                row: 0,
+                start: 1,
+                end: 3
+            }
+        );
+        assert_eq!(schematic.part_numbers_sum(), 12);
+        assert!(schematic.parts_adjacent_to('~')[0].1.is_empty());
+    }
 }
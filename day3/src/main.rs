@@ -1,9 +1,33 @@
+use std::str::FromStr;
+
+use common::Schematic;
+use util::clap::{self, Args};
+
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// List the part numbers touching each occurrence of this symbol, leaving the puzzle unsolved
+    #[arg(long)]
+    adjacent: Option<char>,
+}
+
 fn main() {
-    let cli = util::Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if let Some(symbol) = cli.extras.adjacent {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let schematic = Schematic::from_str(&data).unwrap();
+        for (pos, ids) in schematic.parts_adjacent_to(symbol) {
+            let values: Vec<String> = ids
+                .iter()
+                .map(|&id| schematic.span(id).value.to_string())
+                .collect();
+            println!("symbol={pos:?} parts={}", values.join(","));
+        }
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
@@ -1,33 +1,65 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::str::FromStr;
 
 use anyhow::{anyhow, Error};
 
+/// Numbers below this are stored as bits, the ones above in a sorted list
+const BITSET_CAP: usize = 1024;
+
+/// Set of winning numbers, one bit per number below `BITSET_CAP`
+#[derive(Debug)]
+struct NumberSet {
+    bits: [u64; BITSET_CAP / 64],
+    large: Vec<usize>,
+}
+impl NumberSet {
+    fn contains(&self, n: usize) -> bool {
+        if n < BITSET_CAP {
+            self.bits[n / 64] & (1 << (n % 64)) != 0
+        } else {
+            self.large.binary_search(&n).is_ok()
+        }
+    }
+}
+impl FromStr for NumberSet {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut set = Self {
+            bits: [0; BITSET_CAP / 64],
+            large: vec![],
+        };
+        for n in s.split_whitespace() {
+            match n.parse::<usize>()? {
+                n if n < BITSET_CAP => set.bits[n / 64] |= 1u64 << (n % 64),
+                n => set.large.push(n),
+            }
+        }
+        set.large.sort_unstable();
+        set.large.dedup();
+        Ok(set)
+    }
+}
+
 #[derive(Debug)]
 pub struct Card {
     id: usize,
-    winning_numbers: Vec<usize>,
+    winning_numbers: NumberSet,
     my_numbers: Vec<usize>,
 }
 
 impl Card {
-    pub fn matches(&self) -> Vec<usize> {
+    pub fn nb_matches(&self) -> usize {
+        // Each of my numbers counts, even when it is written twice
         self.my_numbers
             .iter()
-            .filter(|&n| self.winning_numbers.contains(n))
-            .copied()
-            .collect()
+            .filter(|&&n| self.winning_numbers.contains(n))
+            .count()
     }
     pub fn score(&self) -> usize {
-        let matches = self.matches();
-        if !matches.is_empty() {
-            2usize.pow(matches.len() as u32 - 1)
-        } else {
-            0
+        match self.nb_matches() {
+            0 => 0,
+            n => 2usize.pow(n as u32 - 1),
         }
     }
-    pub fn id(&self) -> usize {
-        self.id
-    }
 }
 
 impl FromStr for Card {
@@ -35,39 +67,144 @@ impl FromStr for Card {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
-        let mut s = s.split(':');
-        let card_raw = s
-            .next()
+        let (card_raw, numbers) = s
+            .split_once(':')
             .ok_or(anyhow!("failed to get 'Card X' substring"))?;
         let card_id = card_raw
             .split_whitespace()
             .nth(1)
-            .ok_or(anyhow!("failed to get card id§"))?
+            .ok_or(anyhow!("failed to get card id"))?
             .parse::<usize>()?;
 
-        let numbers = s.next().unwrap();
-        let mut numbers_split = numbers.split('|');
+        let (winning_numbers, my_numbers) = numbers
+            .split_once('|')
+            .ok_or(anyhow!("failed to get winning numbers"))?;
 
-        let winning_numbers: Result<Vec<usize>, ParseIntError> = numbers_split
-            .next()
-            .ok_or(anyhow!("failed to get winning numbers"))?
-            .split_whitespace()
-            .filter(|n| !n.is_empty())
-            .map(|n| n.parse())
-            .collect();
+        Ok(Self {
+            id: card_id,
+            winning_numbers: winning_numbers.parse()?,
+            my_numbers: my_numbers
+                .split_whitespace()
+                .map(|n| n.parse())
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
 
-        let my_numbers: Result<Vec<usize>, ParseIntError> = numbers_split
-            .next()
-            .ok_or(anyhow!("failed to get winning numbers"))?
-            .split_whitespace()
-            .filter(|n| !n.is_empty())
-            .map(|n| n.parse())
-            .collect();
+/// The pile of scratchcards, with the copies won by playing it to the end.
+#[derive(Debug)]
+pub struct Scratchcards {
+    cards: Vec<Card>,
+    /// Number of instances of each card, the original included
+    copies: Vec<usize>,
+    /// For each card, the cards whose instances won copies of it, with how many copies each one won
+    won_from: Vec<Vec<(usize, usize)>>,
+}
+impl FromStr for Scratchcards {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let cards = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| {
+                let card = Card::from_str(line).map_err(|e| anyhow!("line {}: {e}", i + 1))?;
+                if card.id != i + 1 {
+                    return Err(anyhow!(
+                        "line {}: card ids are not contiguous, expected card {} but got card {}",
+                        i + 1,
+                        i + 1,
+                        card.id
+                    ));
+                }
+                Ok(card)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
 
+        // Copies only go to the cards below, so every card has all its instances when it is reached. Copies
+        // past the end of the pile are not won.
+        let mut copies = vec![1; cards.len()];
+        let mut won_from = vec![vec![]; cards.len()];
+        for (i, card) in cards.iter().enumerate() {
+            let last = (i + card.nb_matches()).min(cards.len() - 1);
+            for j in i + 1..=last {
+                copies[j] += copies[i];
+                won_from[j].push((card.id, copies[i]));
+            }
+        }
         Ok(Self {
-            id: card_id,
-            winning_numbers: winning_numbers?,
-            my_numbers: my_numbers?,
+            cards,
+            copies,
+            won_from,
         })
     }
 }
+impl Scratchcards {
+    /// Points of the original cards
+    pub fn points(&self) -> usize {
+        self.cards.iter().map(Card::score).sum()
+    }
+
+    /// Number of cards in the end, originals and copies
+    pub fn nb_cards(&self) -> usize {
+        self.copies.iter().sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    /// Number of instances of card `id`, the original included
+    pub fn copies(&self, id: usize) -> Option<usize> {
+        self.copies.get(id.checked_sub(1)?).copied()
+    }
+
+    /// Cards whose instances won copies of card `id`, with how many copies each one won
+    pub fn won_from(&self, id: usize) -> Option<&[(usize, usize)]> {
+        self.won_from.get(id.checked_sub(1)?).map(Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    #[test]
+    fn test_example() {
+        let cards = Scratchcards::from_str(EXAMPLE).unwrap();
+        assert_eq!(cards.points(), 13);
+        assert_eq!(cards.nb_cards(), 30);
+        let copies: Vec<usize> = (1..=cards.len())
+            .map(|id| cards.copies(id).unwrap())
+            .collect();
+        assert_eq!(copies, [1, 2, 4, 8, 14, 1]);
+        assert_eq!(cards.won_from(1), Some(&[][..]));
+        assert_eq!(cards.won_from(4), Some(&[(1, 1), (2, 2), (3, 4)][..]));
+        assert_eq!(cards.won_from(5), Some(&[(1, 1), (3, 4), (4, 8)][..]));
+        assert_eq!(cards.copies(0), None);
+        assert_eq!(cards.won_from(7), None);
+    }
+
+    #[test]
+    fn test_numbers() {
+        // Large numbers don't grow the bitset, and my numbers count each time they are written
+        let card = Card::from_str("Card 1: 1000000000000 5 | 1000000000000 5 5 7").unwrap();
+        assert_eq!(card.nb_matches(), 3);
+        assert_eq!(card.score(), 4);
+    }
+
+    #[test]
+    fn test_not_contiguous() {
+        let err = Scratchcards::from_str("Card 1: 1 | 1\nCard 3: 1 | 1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: card ids are not contiguous, expected card 2 but got card 3"
+        );
+    }
+}
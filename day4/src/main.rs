@@ -1,9 +1,39 @@
+use std::str::FromStr;
+
+use common::Scratchcards;
+use util::clap::{self, Args};
+
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// Print how many copies of each card were won, and which cards won them, in place of the answers
+    #[arg(long)]
+    copies: bool,
+}
+
 fn main() {
-    let cli = util::Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if cli.extras.copies {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let cards = Scratchcards::from_str(&data).unwrap();
+        for id in 1..=cards.len() {
+            let won_from: Vec<String> = cards
+                .won_from(id)
+                .unwrap_or_default()
+                .iter()
+                .map(|(card, copies)| format!("{card}x{copies}"))
+                .collect();
+            println!(
+                "card={id} copies={} won_from={}",
+                cards.copies(id).unwrap_or_default(),
+                won_from.join(",")
+            );
+        }
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
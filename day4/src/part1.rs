@@ -1,16 +1,9 @@
 use std::str::FromStr;
 
-use crate::common::Card;
+use crate::common::Scratchcards;
 
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    let ans: usize = data
-        .lines()
-        .map(|line| {
-            let card = Card::from_str(line).unwrap();
-            card.score()
-        })
-        .sum();
-
-    ans.to_string()
+    let cards = Scratchcards::from_str(&data).unwrap();
+    cards.points().to_string()
 }
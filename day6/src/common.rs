@@ -1,3 +1,7 @@
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, Error};
+
 #[derive(Debug)]
 pub struct Race {
     time: usize,
@@ -9,15 +13,58 @@ impl Race {
         Self { time, record }
     }
     pub fn nb_ways(&self) -> usize {
-        self.simulation()
-            .iter()
-            .filter(|&dist| *dist > self.record)
-            .count()
+        self.winning_holds()
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    /// Hold times beating the record, found by solving `hold * (time - hold) > record`. The travelled distance
+    /// is symmetric around `time / 2`, so the winning hold times are an interval.
+    pub fn winning_holds(&self) -> Option<RangeInclusive<usize>> {
+        let (time, record) = (self.time as u128, self.record as u128);
+        let wins = |hold: u128| hold * (time - hold) > record;
+        // Roots of hold² - time * hold + record = 0
+        let discriminant = (time * time).checked_sub(4 * record)?;
+        let mut first = (time - discriminant.isqrt()) / 2;
+        // The integer square root rounds down, and a hold time matching the record exactly doesn't win
+        while !wins(first) {
+            first += 1;
+            if first > time / 2 {
+                return None;
+            }
+        }
+        Some(first as usize..=(time - first) as usize)
+    }
+}
+
+/// Values of the line starting with `label`, such as `Time:      7  15   30`
+pub fn field<'a>(line: Option<&'a str>, label: &str) -> Result<&'a str, Error> {
+    line.ok_or(anyhow!("missing '{label}' line"))?
+        .strip_prefix(label)
+        .ok_or(anyhow!("expected a line starting with '{label}'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_winning_holds() {
+        assert_eq!(Race::new(7, 9).winning_holds(), Some(2..=5));
+        assert_eq!(Race::new(15, 40).winning_holds(), Some(4..=11));
+        // Holding 10 or 20ms travels exactly 200mm, which only matches the record
+        assert_eq!(Race::new(30, 200).winning_holds(), Some(11..=19));
+        assert_eq!(Race::new(71530, 940200).nb_ways(), 71503);
+        assert_eq!(Race::new(4, 4).winning_holds(), None);
+        assert_eq!(Race::new(5, 6).winning_holds(), None);
+        assert_eq!(Race::new(0, 0).nb_ways(), 0);
     }
 
-    fn simulation(&self) -> Vec<usize> {
-        (0..self.time)
-            .map(|hold| hold * (self.time - hold))
-            .collect()
+    #[test]
+    fn test_field() {
+        assert_eq!(field(Some("Time:  7 15"), "Time:").unwrap(), "  7 15");
+        let err = field(None, "Distance:").unwrap_err();
+        assert_eq!(err.to_string(), "missing 'Distance:' line");
+        let err = field(Some("Time:  7 15"), "Distance:").unwrap_err();
+        assert_eq!(err.to_string(), "expected a line starting with 'Distance:'");
     }
 }
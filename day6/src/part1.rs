@@ -1,8 +1,8 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::str::FromStr;
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
-use crate::common::Race;
+use crate::common::{field, Race};
 
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
@@ -24,28 +24,25 @@ impl FromStr for Puzzle {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
-        let times: Result<Vec<usize>, ParseIntError> = lines
-            .next()
-            .unwrap()
-            .strip_prefix("Time:")
-            .unwrap()
+        let times = field(lines.next(), "Time:")?
             .split_whitespace()
-            .filter(|&c| !c.is_empty()) //remove whitespaces only
             .map(|c| c.parse())
-            .collect();
-        let records: Result<Vec<usize>, ParseIntError> = lines
-            .next()
-            .unwrap()
-            .strip_prefix("Distance:")
-            .unwrap()
+            .collect::<Result<Vec<usize>, _>>()?;
+        let records = field(lines.next(), "Distance:")?
             .split_whitespace()
-            .filter(|&c| !c.is_empty()) //remove whitespaces only
             .map(|c| c.parse())
-            .collect();
+            .collect::<Result<Vec<usize>, _>>()?;
+        if times.len() != records.len() {
+            return Err(anyhow!(
+                "{} times but {} distances",
+                times.len(),
+                records.len()
+            ));
+        }
         Ok(Self(
-            times?
+            times
                 .into_iter()
-                .zip(records?)
+                .zip(records)
                 .map(|(time, record)| Race::new(time, record))
                 .collect(),
         ))
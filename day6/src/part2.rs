@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use anyhow::Error;
 
-use crate::common::Race;
+use crate::common::{field, Race};
 
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
@@ -24,21 +24,8 @@ impl FromStr for Puzzle {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
-        let time: usize = lines
-            .next()
-            .unwrap()
-            .strip_prefix("Time:")
-            .unwrap()
-            .replace(' ', "")
-            .parse()?;
-
-        let record: usize = lines
-            .next()
-            .unwrap()
-            .strip_prefix("Distance:")
-            .unwrap()
-            .replace(' ', "")
-            .parse()?;
+        let time: usize = field(lines.next(), "Time:")?.replace(' ', "").parse()?;
+        let record: usize = field(lines.next(), "Distance:")?.replace(' ', "").parse()?;
         Ok(Self(Race::new(time, record)))
     }
 }
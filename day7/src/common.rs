@@ -1,199 +1,482 @@
-use std::str::FromStr;
+use std::{cmp::Ordering, collections::HashMap, fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, Error};
 
-#[derive(Debug, PartialEq, PartialOrd)]
-struct Card<const J: usize>(usize);
-impl<const J: usize> TryFrom<char> for Card<J> {
-    type Error = Error;
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            c @ '2'..='9' => Ok(Self(
-                c.to_digit(10).ok_or(anyhow!("fail to convert to digit"))? as usize,
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TieBreak {
+    /// Compare the cards one by one, in the order they were dealt
+    FirstCard,
+    /// Compare the cards one by one, from the strongest of each hand
+    HighestCard,
+}
+impl FromStr for TieBreak {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "first-card" => Ok(Self::FirstCard),
+            "highest-card" => Ok(Self::HighestCard),
+            _ => Err(anyhow!(
+                "unknown tie-break '{s}', expected first-card or highest-card"
             )),
-            'T' => Ok(Self(10)),
-            'J' => Ok(Self(J)),
-            'Q' => Ok(Self(12)),
-            'K' => Ok(Self(13)),
-            'A' => Ok(Self(14)),
-            _ => Err(anyhow!("character '{value}' can't be converted to a Card")),
         }
     }
 }
-impl<const J: usize> Card<J> {
-    pub fn value(&self) -> usize {
-        self.0
-    }
+
+/// How hands are read, classified and ranked.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// Cards from the weakest to the strongest
+    order: Vec<char>,
+    /// Cards standing for whichever card makes the best hand type. They keep their own strength to break ties.
+    wildcards: Vec<char>,
+    hand_size: usize,
+    tie_break: TieBreak,
 }
+impl Rules {
+    pub fn new(
+        order: &str,
+        wildcards: &str,
+        hand_size: usize,
+        tie_break: TieBreak,
+    ) -> Result<Self, Error> {
+        let order: Vec<char> = order.chars().collect();
+        if let Some(c) = order
+            .iter()
+            .enumerate()
+            .find_map(|(i, c)| order[..i].contains(c).then_some(c))
+        {
+            return Err(anyhow!("card '{c}' appears twice in the card order"));
+        }
+        let wildcards: Vec<char> = wildcards.chars().collect();
+        if let Some(c) = wildcards.iter().find(|c| !order.contains(c)) {
+            return Err(anyhow!("wildcard '{c}' is not in the card order"));
+        }
+        if hand_size == 0 {
+            return Err(anyhow!("hands must hold at least one card"));
+        }
+        Ok(Self {
+            order,
+            wildcards,
+            hand_size,
+            tie_break,
+        })
+    }
 
-#[derive(Debug)]
-struct Hand<const J: usize>([Card<J>; 5]);
-impl<const J: usize> FromStr for Hand<J> {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Q97J7
-        let hand: Result<Vec<Card<J>>, Error> = s.chars().take(5).map(|c| c.try_into()).collect();
-        Ok(Self(hand?.try_into().unwrap()))
+    /// Camel Cards, as played in part 1
+    pub fn standard() -> Self {
+        Self::new("23456789TJQKA", "", 5, TieBreak::FirstCard).unwrap()
     }
-}
-impl<const J: usize> Hand<J> {
-    fn hand_type(&self) -> HandType {
-        self.into()
+
+    /// Camel Cards where J are jokers, the weakest cards but wildcards, as played in part 2
+    pub fn joker() -> Self {
+        Self::new("J23456789TQKA", "J", 5, TieBreak::FirstCard).unwrap()
     }
-}
 
-impl<const J: usize> PartialEq for Hand<J> {
-    fn eq(&self, _other: &Self) -> bool {
-        false
+    fn strength(&self, card: char) -> Option<usize> {
+        self.order.iter().position(|&c| c == card)
     }
-}
 
-impl<const J: usize> PartialOrd for Hand<J> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    fn is_wildcard(&self, card: char) -> bool {
+        self.wildcards.contains(&card)
     }
-}
-impl<const J: usize> Ord for Hand<J> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.hand_type() != other.hand_type() {
-            return self.hand_type().cmp(&other.hand_type());
-        }
 
-        for (c1, c2) in self.0.iter().zip(other.0.iter()) {
-            if c1 > c2 {
-                return std::cmp::Ordering::Greater;
-            }
-            if c1 < c2 {
-                return std::cmp::Ordering::Less;
-            }
-        }
-        std::cmp::Ordering::Equal
+    /// Strongest card that is not a wildcard
+    fn best_card(&self) -> Option<char> {
+        self.order
+            .iter()
+            .rev()
+            .find(|&&c| !self.is_wildcard(c))
+            .copied()
     }
 }
-impl<const J: usize> Eq for Hand<J> {
-    fn assert_receiver_is_total_eq(&self) {}
-}
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-enum HandType {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandType {
     HighCard = 0,
     OnePair = 1,
     TwoPair = 2,
     ThreeOfAKind = 3,
     FullHouse = 4,
     FourOfAKind = 5,
+    /// Five or more of a kind, for hands larger than 5 cards
     FiveOfAKind = 6,
 }
+impl Display for HandType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::HighCard => "high card",
+            Self::OnePair => "one pair",
+            Self::TwoPair => "two pair",
+            Self::ThreeOfAKind => "three of a kind",
+            Self::FullHouse => "full house",
+            Self::FourOfAKind => "four of a kind",
+            Self::FiveOfAKind => "five of a kind",
+        };
+        write!(f, "{s}")
+    }
+}
 impl HandType {
-    fn is_two_pair(reps: &[usize; 14]) -> bool {
-        /*
-            2, 2
-            2, 1+1J         <- not possible, 3 of a kind
-            2, 2J           <- not possible, 4 of a kind
-            1J+1J, 1J+1     <- not possible, 4 of a kind
-            1J+1J, 1J+1J    <- not possible, 4 of a kind
-        */
-        reps.iter().filter(|&rep| *rep == 2).count() == 2
-    }
-    fn is_full_house(reps: &[usize; 14]) -> bool {
-        /*
-
-            2, 3
-            2, 2+1J
-            2, 1+2J     -> not possible, 4 of a kind
-            2, 3J       -> not possible, 5 of a kind
-
-            1+1J, 3     -> not possible, 4 of a kind
-            1+1J, 2+1J  -> not possible, 4 of a kind
-            1+1J, 1+2J  -> not possible, 4 of a kind
-            1+1J, 3J    -> not possible, 5 of a kind
-
-            2J, 3       -> not possible, 5 of a kind
-            2J, 2+1J    -> not possible, 5 of a kind
-            2J, 1+2J    -> not possible, 5 of a kind
-            2J, 3J      -> not possible, 5 of a kind
-        */
-        let nb_wildcards = reps[0];
-        let nb_pairs = reps.iter().skip(1).filter(|&rep| *rep == 2).count();
-        let nb_three_of_a_kind = reps.iter().skip(1).filter(|&rep| *rep == 3).count();
-        (nb_pairs == 1 && nb_three_of_a_kind == 1) || (nb_pairs == 2 && nb_wildcards == 1)
-    }
-}
-impl<const J: usize> From<&Hand<J>> for HandType {
-    fn from(value: &Hand<J>) -> Self {
-        let mut reps = [0usize; 14];
-        value.0.iter().for_each(|card| {
-            reps[card.value() - 1] += 1;
-        });
-        let nb_wildcards = reps[0];
-        match reps {
-            _ if reps.iter().skip(1).any(|&rep| rep + nb_wildcards == 5) => HandType::FiveOfAKind,
-            _ if reps.iter().skip(1).any(|&rep| rep + nb_wildcards == 4) => HandType::FourOfAKind,
-            _ if Self::is_full_house(&reps) => HandType::FullHouse,
-            _ if reps.iter().skip(1).any(|&rep| rep + nb_wildcards == 3) => HandType::ThreeOfAKind,
-            _ if Self::is_two_pair(&reps) => HandType::TwoPair,
-            _ if reps.iter().skip(1).any(|&rep| rep + nb_wildcards == 2) => HandType::OnePair,
-            _ => HandType::HighCard,
+    /// Type of a hand from the sizes of its two largest groups of identical cards
+    fn from_groups(largest: usize, second: usize) -> Self {
+        match (largest, second) {
+            (5.., _) => Self::FiveOfAKind,
+            (4, _) => Self::FourOfAKind,
+            (3, 2..) => Self::FullHouse,
+            (3, _) => Self::ThreeOfAKind,
+            (2, 2) => Self::TwoPair,
+            (2, _) => Self::OnePair,
+            _ => Self::HighCard,
         }
     }
 }
 
+/// A hand, classified and ranked under some `Rules`. Hands are equal when they are equally strong.
 #[derive(Debug)]
-pub struct Game<const J: usize> {
-    hand: Hand<J>,
-    bid: usize,
+struct Hand {
+    cards: String,
+    hand_type: HandType,
+    /// Card the wildcards of the hand stand for, if it has any
+    substitution: Option<char>,
+    /// Strengths of the cards, in the order they are compared to break ties
+    tie_break: Vec<usize>,
 }
-impl<const J: usize> FromStr for Game<J> {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        // Hand  Bid
-        // Q97J7 740
-        let mut s = s.split_whitespace();
-        let hand = Hand::from_str(s.next().unwrap())?;
-        let bid = s.next().unwrap().parse()?;
-        Ok(Self { hand, bid })
+impl Hand {
+    fn parse(s: &str, rules: &Rules) -> Result<Self, Error> {
+        // Q97J7
+        let strengths = s
+            .chars()
+            .map(|c| {
+                rules
+                    .strength(c)
+                    .ok_or(anyhow!("character '{c}' can't be converted to a Card"))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if strengths.len() != rules.hand_size {
+            return Err(anyhow!(
+                "hand '{s}' has {} cards instead of {}",
+                strengths.len(),
+                rules.hand_size
+            ));
+        }
+
+        // Wildcards always make the best hand by joining the largest group
+        // (the strongest of them on equal sizes)
+        let mut groups: HashMap<char, usize> = HashMap::new();
+        s.chars()
+            .filter(|&c| !rules.is_wildcard(c))
+            .for_each(|c| *groups.entry(c).or_default() += 1);
+        let mut groups: Vec<(char, usize)> = groups.into_iter().collect();
+        groups.sort_unstable_by_key(|&(c, size)| std::cmp::Reverse((size, rules.strength(c))));
+        let nb_wildcards = s.chars().filter(|&c| rules.is_wildcard(c)).count();
+        let largest = groups.first().map_or(0, |&(_, size)| size) + nb_wildcards;
+        let second = groups.get(1).map_or(0, |&(_, size)| size);
+        let substitution = (nb_wildcards > 0)
+            .then(|| groups.first().map(|&(c, _)| c).or(rules.best_card()))
+            .flatten();
+
+        let mut tie_break = strengths;
+        if rules.tie_break == TieBreak::HighestCard {
+            tie_break.sort_unstable_by(|a, b| b.cmp(a));
+        }
+        Ok(Self {
+            cards: s.to_string(),
+            hand_type: HandType::from_groups(largest, second),
+            substitution,
+            tie_break,
+        })
     }
 }
-impl<const J: usize> Game<J> {
-    pub fn bid(&self) -> usize {
-        self.bid
+impl PartialEq for Hand {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
     }
 }
-impl<const J: usize> PartialEq for Game<J> {
-    fn eq(&self, other: &Self) -> bool {
-        self.hand.eq(&other.hand)
+impl Eq for Hand {}
+impl PartialOrd for Hand {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
-impl<const J: usize> Eq for Game<J> {
-    fn assert_receiver_is_total_eq(&self) {}
+impl Ord for Hand {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.hand_type
+            .cmp(&other.hand_type)
+            .then_with(|| self.tie_break.cmp(&other.tie_break))
+    }
+}
+
+/// A hand and its bid. Games are ordered by their hand only, the bid doesn't take part in the ranking.
+#[derive(Debug)]
+pub struct Game {
+    hand: Hand,
+    bid: usize,
+}
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.hand == other.hand
+    }
 }
-impl<const J: usize> PartialOrd for Game<J> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl Eq for Game {}
+impl PartialOrd for Game {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
-impl<const J: usize> Ord for Game<J> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+impl Ord for Game {
+    fn cmp(&self, other: &Self) -> Ordering {
         self.hand.cmp(&other.hand)
     }
 }
+impl Game {
+    fn parse(s: &str, rules: &Rules) -> Result<Self, Error> {
+        // Hand  Bid
+        // Q97J7 740
+        let (hand, bid) = s
+            .split_once(' ')
+            .ok_or(anyhow!("expected a hand and a bid, got '{s}'"))?;
+        let hand = Hand::parse(hand, rules)?;
+        let bid = bid.trim().parse()?;
+        Ok(Self { hand, bid })
+    }
+
+    pub fn bid(&self) -> usize {
+        self.bid
+    }
+}
 
 #[derive(Debug)]
-pub struct Puzzle<const J: usize>(Vec<Game<J>>);
-impl<const J: usize> FromStr for Puzzle<J> {
+pub struct Puzzle(Vec<Game>);
+impl Puzzle {
+    pub fn parse(s: &str, rules: &Rules) -> Result<Self, Error> {
+        let puzzle: Result<Vec<Game>, Error> = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| Game::parse(line, rules).map_err(|e| anyhow!("line {}: {e}", i + 1)))
+            .collect();
+        Ok(Self(puzzle?))
+    }
+
+    /// Games from the weakest to the strongest hand, with how they were classified and what they won
+    pub fn ranking(&mut self) -> Ranking {
+        self.0.sort();
+        Ranking(
+            self.0
+                .iter()
+                .enumerate()
+                .map(|(i, game)| RankedGame {
+                    rank: i + 1,
+                    hand: game.hand.cards.clone(),
+                    hand_type: game.hand.hand_type,
+                    substitution: game.hand.substitution,
+                    bid: game.bid(),
+                    winnings: (i + 1) * game.bid(),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn run(&mut self) -> usize {
+        self.ranking().winnings()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableFormat {
+    Text,
+    Csv,
+}
+impl FromStr for TableFormat {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let puzzle: Result<Vec<Game<J>>, Error> = s.lines().map(Game::from_str).collect();
-        Ok(Self(puzzle?))
+        match s {
+            "text" => Ok(Self::Text),
+            "csv" => Ok(Self::Csv),
+            _ => Err(anyhow!("unknown table format '{s}', expected text or csv")),
+        }
     }
 }
-impl<const J: usize> Puzzle<J> {
-    pub fn run(&mut self) -> usize {
-        self.0.sort();
-        self.0
-            .iter()
-            .enumerate()
-            .map(|(rank, game)| (rank + 1) * game.bid())
-            .sum()
+
+#[derive(Debug)]
+pub struct RankedGame {
+    rank: usize,
+    hand: String,
+    hand_type: HandType,
+    substitution: Option<char>,
+    bid: usize,
+    winnings: usize,
+}
+
+#[derive(Debug)]
+pub struct Ranking(Vec<RankedGame>);
+impl Ranking {
+    /// Total winnings of the games
+    pub fn winnings(&self) -> usize {
+        self.0.iter().map(|game| game.winnings).sum()
+    }
+
+    pub fn render(&self, format: TableFormat) -> String {
+        let substitution = |game: &RankedGame| game.substitution.map(String::from);
+        let mut s = String::new();
+        match format {
+            TableFormat::Text => {
+                let hand_width = self
+                    .0
+                    .iter()
+                    .map(|g| g.hand.len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(4);
+                s.push_str(&format!(
+                    "{:>5}  {:<hand_width$}  {:<15}  {:<5}  {:>6}  {:>9}\n",
+                    "rank", "hand", "type", "joker", "bid", "winnings"
+                ));
+                for game in self.0.iter() {
+                    s.push_str(&format!(
+                        "{:>5}  {:<hand_width$}  {:<15}  {:<5}  {:>6}  {:>9}\n",
+                        game.rank,
+                        game.hand,
+                        game.hand_type.to_string(),
+                        substitution(game).unwrap_or("-".to_string()),
+                        game.bid,
+                        game.winnings
+                    ));
+                }
+            }
+            TableFormat::Csv => {
+                s.push_str("rank,hand,type,joker,bid,winnings\n");
+                for game in self.0.iter() {
+                    s.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        game.rank,
+                        csv_field(&game.hand),
+                        game.hand_type,
+                        csv_field(&substitution(game).unwrap_or_default()),
+                        game.bid,
+                        game.winnings
+                    ));
+                }
+            }
+        }
+        s
+    }
+}
+
+/// `field` quoted for CSV if it holds a comma, a quote or a line break, with its quotes doubled
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
+    #[test]
+    fn test_example() {
+        let mut puzzle = Puzzle::parse(EXAMPLE, &Rules::standard()).unwrap();
+        assert_eq!(puzzle.run(), 6440);
+        let mut puzzle = Puzzle::parse(EXAMPLE, &Rules::joker()).unwrap();
+        assert_eq!(puzzle.run(), 5905);
+    }
+
+    #[test]
+    fn test_hand_type() {
+        let hand_type = |s: &str, rules: &Rules| Hand::parse(s, rules).unwrap().hand_type;
+        assert_eq!(hand_type("KTJJT", &Rules::standard()), HandType::TwoPair);
+        assert_eq!(hand_type("KTJJT", &Rules::joker()), HandType::FourOfAKind);
+        assert_eq!(hand_type("2JJ3J", &Rules::joker()), HandType::FourOfAKind);
+        assert_eq!(hand_type("JJJJJ", &Rules::joker()), HandType::FiveOfAKind);
+        assert_eq!(hand_type("2233J", &Rules::joker()), HandType::FullHouse);
+
+        let rules = Rules::new("123", "", 3, TieBreak::FirstCard).unwrap();
+        assert_eq!(hand_type("121", &rules), HandType::OnePair);
+        assert_eq!(hand_type("333", &rules), HandType::ThreeOfAKind);
+    }
+
+    #[test]
+    fn test_eq_and_ord() {
+        let rules = Rules::standard();
+        let hand = |s: &str| Hand::parse(s, &rules).unwrap();
+        assert_eq!(hand("KK677"), hand("KK677"));
+        assert_ne!(hand("KK677"), hand("KTJJT"));
+        assert!(hand("KK677") > hand("KTJJT"));
+
+        // With the highest card first, the order in which the cards were dealt doesn't matter
+        let rules = Rules::new("23456789TJQKA", "", 5, TieBreak::HighestCard).unwrap();
+        let hand = |s: &str| Hand::parse(s, &rules).unwrap();
+        assert_eq!(hand("A2345"), hand("5432A"));
+        assert!(hand("A2345") < hand("2346A"));
+
+        // The bid doesn't take part in the ranking
+        let game = |s: &str| Game::parse(s, &rules).unwrap();
+        assert_eq!(game("A2345 1").cmp(&game("5432A 9")), Ordering::Equal);
+        assert!(game("A2345 9") < game("2346A 1"));
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut puzzle = Puzzle::parse(EXAMPLE, &Rules::joker()).unwrap();
+        assert_eq!(
+            puzzle.ranking().render(TableFormat::Text),
+            " rank  hand   type             joker     bid   winnings
+    1  32T3K  one pair         -         765        765
+    2  KK677  two pair         -          28         56
+    3  T55J5  four of a kind   5         684       2052
+    4  QQQJA  four of a kind   Q         483       1932
+    5  KTJJT  four of a kind   T         220       1100
+"
+        );
+        assert_eq!(
+            puzzle.ranking().render(TableFormat::Csv),
+            "rank,hand,type,joker,bid,winnings
+1,32T3K,one pair,,765,765
+2,KK677,two pair,,28,56
+3,T55J5,four of a kind,5,684,2052
+4,QQQJA,four of a kind,Q,483,1932
+5,KTJJT,four of a kind,T,220,1100
+"
+        );
+
+        let mut puzzle = Puzzle::parse("JJJJJ 1", &Rules::joker()).unwrap();
+        assert!(puzzle
+            .ranking()
+            .render(TableFormat::Csv)
+            .ends_with("1,JJJJJ,five of a kind,A,1,1\n"));
+
+        // House cards which are CSV separators or quotes
+        let rules = Rules::new("a,\"", "\"", 2, TieBreak::FirstCard).unwrap();
+        let mut puzzle = Puzzle::parse(",\" 3\naa 1", &rules).unwrap();
+        assert_eq!(
+            puzzle.ranking().render(TableFormat::Csv),
+            "rank,hand,type,joker,bid,winnings
+1,aa,one pair,,1,1
+2,\",\"\"\",one pair,\",\",3,6
+"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = Rules::new("23456789TJQKA", "X", 5, TieBreak::FirstCard).unwrap_err();
+        assert_eq!(err.to_string(), "wildcard 'X' is not in the card order");
+        let err = Puzzle::parse("32T3K 765\nKK67 28", &Rules::standard()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: hand 'KK67' has 4 cards instead of 5"
+        );
+        let err = Puzzle::parse("32T3X 765", &Rules::standard()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1: character 'X' can't be converted to a Card"
+        );
     }
 }
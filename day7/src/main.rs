@@ -1,11 +1,55 @@
-use util::Cli;
+use common::{Puzzle, Rules, TableFormat, TieBreak};
+use util::clap::{self, Args};
 
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct HouseRules {
+    /// Cards of a house variant to score, from the weakest to the strongest
+    #[arg(long)]
+    order: Option<String>,
+    /// Cards of the house variant standing for whichever card makes the best hand
+    #[arg(long, default_value = "")]
+    wildcards: String,
+    /// Number of cards per hand of the house variant
+    #[arg(long, default_value_t = 5)]
+    hand_size: usize,
+    /// How the house variant breaks ties between hands of the same type: first-card or highest-card
+    #[arg(long, default_value = "first-card")]
+    tie_break: TieBreak,
+    /// Print the ranked hands as a text or csv table rather than the winnings
+    #[arg(long)]
+    table: Option<TableFormat>,
+    /// Rank the table with the joker rules of part 2
+    #[arg(long)]
+    joker: bool,
+}
+
 fn main() {
-    let cli = Cli::get();
+    let cli = util::Cli::<HouseRules>::with_extras();
+    let house = &cli.extras;
+    let house_rules = house.order.as_ref().map(|order| {
+        Rules::new(order, &house.wildcards, house.hand_size, house.tie_break).unwrap()
+    });
+    if let Some(format) = house.table {
+        let rules = match house_rules {
+            Some(rules) => rules,
+            None if house.joker => Rules::joker(),
+            None => Rules::standard(),
+        };
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let mut puzzle = Puzzle::parse(&data, &rules).unwrap();
+        print!("{}", puzzle.ranking().render(format));
+        return;
+    }
+    if let Some(rules) = house_rules {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let mut puzzle = Puzzle::parse(&data, &rules).unwrap();
+        println!("winnings={}", puzzle.run());
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
@@ -6,6 +6,14 @@ pub enum Instruction {
     Left,
     Right,
 }
+impl Instruction {
+    fn index(&self) -> usize {
+        match self {
+            Self::Left => 0,
+            Self::Right => 1,
+        }
+    }
+}
 
 impl TryFrom<char> for Instruction {
     type Error = Error;
@@ -18,44 +26,169 @@ impl TryFrom<char> for Instruction {
     }
 }
 
-#[derive(Debug)]
-pub struct Node {
-    right: String,
-    left: String,
+/// Parse a puzzle: the instructions line, an empty line, then the network
+pub fn parse(s: &str) -> Result<(Vec<Instruction>, Map), Error> {
+    let mut lines = s.lines();
+    let instructions = parse_instructions(lines.next().unwrap_or_default())?;
+    lines.next();
+    let map = Map::from_str(&lines.collect::<Vec<_>>().join("\n"))?;
+    Ok((instructions, map))
+}
+
+/// Parse the instructions line, which must not be empty since the instructions are followed in a loop
+pub fn parse_instructions(line: &str) -> Result<Vec<Instruction>, Error> {
+    if line.is_empty() {
+        return Err(anyhow!("line 1: no instructions"));
+    }
+    line.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            Instruction::try_from(c).map_err(|e| anyhow!("line 1, column {}: {e}", i + 1))
+        })
+        .collect()
 }
 
+/// Network of nodes, interned into dense indices in the order they are defined.
 #[derive(Debug)]
-pub struct Map(HashMap<String, Node>);
+pub struct Map {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    /// Left and right successors of each node
+    successors: Vec<[usize; 2]>,
+}
 impl Map {
-    pub fn left(&self, element: &str) -> &str {
-        &self.0[element].left
+    pub fn len(&self) -> usize {
+        self.names.len()
     }
-    pub fn right(&self, element: &str) -> &str {
-        &self.0[element].right
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.index.get(name).copied()
     }
-}
-impl AsRef<HashMap<String, Node>> for Map {
-    fn as_ref(&self) -> &HashMap<String, Node> {
-        &self.0
+    pub fn name(&self, node: usize) -> &str {
+        &self.names[node]
+    }
+    /// Nodes whose name ends with `c`
+    pub fn nodes_ending_with(&self, c: char) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(move |&node| self.names[node].ends_with(c))
+    }
+    pub fn next(&self, node: usize, instruction: &Instruction) -> usize {
+        self.successors[node][instruction.index()]
+    }
+
+    /// Graphviz description of the network, with an edge per instruction
+    pub fn to_dot(&self) -> String {
+        let mut s = String::from("digraph network {\n");
+        for (node, [left, right]) in self.successors.iter().enumerate() {
+            let name = &self.names[node];
+            if left == right {
+                s.push_str(&format!(
+                    "    \"{name}\" -> \"{}\" [label=\"L,R\"];\n",
+                    self.names[*left]
+                ));
+            } else {
+                s.push_str(&format!(
+                    "    \"{name}\" -> \"{}\" [label=\"L\"];\n",
+                    self.names[*left]
+                ));
+                s.push_str(&format!(
+                    "    \"{name}\" -> \"{}\" [label=\"R\"];\n",
+                    self.names[*right]
+                ));
+            }
+        }
+        s.push_str("}\n");
+        s
     }
 }
 impl FromStr for Map {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         //AAA = (BBB, BBB)
-        let map = s
-            .lines()
-            .map(|line| {
-                let mut sp = line.split('=');
-                let entry = sp.next().unwrap().trim().to_string();
-                let node_sp = sp.next().unwrap().replace(['(', ')'], "");
-                let mut node_sp = node_sp.split(',');
-                let left = node_sp.next().unwrap().trim().to_string();
-                let right = node_sp.next().unwrap().trim().to_string();
-                let node = Node { right, left };
-                (entry, node)
+        let mut names = vec![];
+        let mut index = HashMap::new();
+        let mut definitions = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let err = |msg: String| anyhow!("line {}: {msg}", i + 1);
+            let (entry, node) = line
+                .split_once('=')
+                .ok_or_else(|| err(format!("expected 'NODE = (LEFT, RIGHT)', got '{line}'")))?;
+            let (left, right) = node
+                .trim()
+                .strip_prefix('(')
+                .and_then(|node| node.strip_suffix(')'))
+                .and_then(|node| node.split_once(','))
+                .ok_or_else(|| err(format!("expected '(LEFT, RIGHT)', got '{}'", node.trim())))?;
+            let entry = entry.trim().to_string();
+            if index.insert(entry.clone(), names.len()).is_some() {
+                return Err(err(format!("node '{entry}' is defined twice")));
+            }
+            names.push(entry);
+            definitions.push((i, left.trim(), right.trim()));
+        }
+
+        let successors = definitions
+            .into_iter()
+            .map(|(i, left, right)| {
+                let resolve = |name: &str| {
+                    index
+                        .get(name)
+                        .copied()
+                        .ok_or_else(|| anyhow!("line {}: node '{name}' is not defined", i + 1))
+                };
+                Ok([resolve(left)?, resolve(right)?])
             })
-            .collect();
-        Ok(Self(map))
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            names,
+            index,
+            successors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let map = Map::from_str("AAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)").unwrap();
+        let (aaa, bbb) = (map.index("AAA").unwrap(), map.index("BBB").unwrap());
+        assert_eq!(map.next(aaa, &Instruction::Left), bbb);
+        assert_eq!(map.name(map.next(bbb, &Instruction::Right)), "ZZZ");
+        assert_eq!(
+            map.to_dot(),
+            r#"digraph network {
+    "AAA" -> "BBB" [label="L,R"];
+    "BBB" -> "AAA" [label="L"];
+    "BBB" -> "ZZZ" [label="R"];
+    "ZZZ" -> "ZZZ" [label="L,R"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_instructions() {
+        assert_eq!(parse_instructions("LRL").unwrap().len(), 3);
+        let err = parse_instructions("").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: no instructions");
+        let err = parse_instructions("LRX").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 1, column 3: can't convert char to instruction"
+        );
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let err = Map::from_str("AAA = (BBB, CCC)\nBBB = (AAA, AAA)").unwrap_err();
+        assert_eq!(err.to_string(), "line 1: node 'CCC' is not defined");
+        let err = Map::from_str("AAA = (AAA, AAA)\nAAA (AAA, AAA)").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2: expected 'NODE = (LEFT, RIGHT)', got 'AAA (AAA, AAA)'"
+        );
+        let err = Map::from_str("AAA = (AAA, AAA)\nAAA = (AAA, AAA)").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: node 'AAA' is defined twice");
     }
 }
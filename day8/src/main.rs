@@ -1,11 +1,24 @@
-use util::Cli;
+use util::clap::{self, Args};
 
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// Print the network of nodes in the DOT format of Graphviz, for drawing it, without solving
+    #[arg(long)]
+    dot: bool,
+}
+
 fn main() {
-    let cli = Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if cli.extras.dot {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let (_, map) = common::parse(&data).unwrap();
+        print!("{}", map.to_dot());
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
@@ -1,8 +1,8 @@
 use std::str::FromStr;
 
-use anyhow::{Error, Ok};
+use anyhow::{anyhow, Error, Ok};
 
-use crate::common::{Instruction, Map};
+use crate::common::{parse, Instruction, Map};
 
 #[derive(Debug)]
 struct Puzzle {
@@ -12,41 +12,54 @@ struct Puzzle {
 impl FromStr for Puzzle {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let instructions: Result<Vec<Instruction>, Error> = lines
-            .next()
-            .unwrap()
-            .chars()
-            .map(|c| c.try_into())
-            .collect();
-        lines.next();
-        let rest: String = lines.collect::<Vec<_>>().join("\n");
-        let map = Map::from_str(&rest);
-        Ok(Self {
-            instructions: instructions?,
-            map: map?,
-        })
+        let (instructions, map) = parse(s)?;
+        Ok(Self { instructions, map })
     }
 }
 impl Puzzle {
-    pub fn run(&self) -> usize {
-        let mut nb_steps = 0;
-        let mut next_element = "AAA";
-        for instruction in self.instructions.iter().cycle() {
-            match instruction {
-                Instruction::Left => next_element = self.map.left(next_element),
-                Instruction::Right => next_element = self.map.right(next_element),
+    pub fn run(&self) -> Result<usize, Error> {
+        let start = self.map.index("AAA").ok_or(anyhow!("no node AAA"))?;
+        let end = self.map.index("ZZZ").ok_or(anyhow!("no node ZZZ"))?;
+        // The path is deterministic: once a (node, instruction index) state repeats, it loops without reaching ZZZ
+        let nb_instructions = self.instructions.len();
+        let mut seen = vec![false; self.map.len() * nb_instructions];
+        let mut node = start;
+        for (nb_steps, (index, instruction)) in
+            self.instructions.iter().enumerate().cycle().enumerate()
+        {
+            let state = node * nb_instructions + index;
+            if seen[state] {
+                return Err(anyhow!("ZZZ can't be reached from AAA"));
             }
-            nb_steps += 1;
-            if next_element == "ZZZ" {
-                break;
+            seen[state] = true;
+            node = self.map.next(node, instruction);
+            if node == end {
+                return Ok(nb_steps + 1);
             }
         }
-        nb_steps
+        unreachable!("the instructions are cycled forever")
     }
 }
 
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    Puzzle::from_str(&data).unwrap().run().to_string()
+    Puzzle::from_str(&data).unwrap().run().unwrap().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unreachable() {
+        let puzzle = Puzzle::from_str(
+            "RL\n\nAAA = (BBB, CCC)\nBBB = (AAA, AAA)\nCCC = (ZZZ, AAA)\nZZZ = (ZZZ, ZZZ)",
+        )
+        .unwrap();
+        assert_eq!(puzzle.run().unwrap(), 2);
+        let puzzle =
+            Puzzle::from_str("L\n\nAAA = (BBB, ZZZ)\nBBB = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)").unwrap();
+        let err = puzzle.run().unwrap_err();
+        assert_eq!(err.to_string(), "ZZZ can't be reached from AAA");
+    }
 }
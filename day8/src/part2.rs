@@ -2,8 +2,8 @@ use std::str::FromStr;
 
 use anyhow::{Error, Ok};
 
-use crate::common::{Instruction, Map};
-use num::integer::lcm;
+use crate::common::{parse, Instruction, Map};
+use num::{integer::ExtendedGcd, Integer};
 
 #[derive(Debug)]
 struct Puzzle {
@@ -13,54 +13,208 @@ struct Puzzle {
 impl FromStr for Puzzle {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.lines();
-        let instructions: Result<Vec<Instruction>, Error> = lines
-            .next()
-            .unwrap()
-            .chars()
-            .map(|c| c.try_into())
-            .collect();
-        lines.next();
-        let rest: String = lines.collect::<Vec<_>>().join("\n");
-        let map = Map::from_str(&rest);
-        Ok(Self {
-            instructions: instructions?,
-            map: map?,
-        })
+        let (instructions, map) = parse(s)?;
+        Ok(Self { instructions, map })
     }
 }
 impl Puzzle {
-    pub fn run(&self) -> usize {
-        let elements = self
+    pub fn run(&self) -> Option<usize> {
+        let ghosts: Vec<GhostCycle> = self
             .map
-            .as_ref()
-            .keys()
-            .filter(|&key| key.ends_with('A'))
-            .map(|key| key.as_str());
-
-        let mut total_steps = 1;
-        for step in elements.map(|element| self.nb_steps(element)) {
-            total_steps = lcm(total_steps, step)
-        }
-        total_steps
+            .nodes_ending_with('A')
+            .map(|node| self.cycle(node))
+            .collect();
+        GhostCycle::first_common_z_step(&ghosts)
     }
-    fn nb_steps<'a>(&'a self, mut next_element: &'a str) -> usize {
-        let mut nb_steps = 0;
-        for instruction in self.instructions.iter().cycle() {
-            match instruction {
-                Instruction::Left => next_element = self.map.left(next_element),
-                Instruction::Right => next_element = self.map.right(next_element),
+
+    /// Follow the ghost starting on `start` until it reaches a (node, instruction index) state for the second time
+    fn cycle(&self, start: usize) -> GhostCycle {
+        let nb_instructions = self.instructions.len();
+        let mut seen: Vec<Option<usize>> = vec![None; self.map.len() * nb_instructions];
+        let is_z: Vec<bool> = (0..self.map.len())
+            .map(|node| self.map.name(node).ends_with('Z'))
+            .collect();
+        let mut z_offsets = vec![];
+        let mut node = start;
+        for (step, (index, instruction)) in self.instructions.iter().enumerate().cycle().enumerate()
+        {
+            let state = node * nb_instructions + index;
+            if let Some(prefix) = seen[state] {
+                return GhostCycle {
+                    prefix,
+                    length: step - prefix,
+                    z_offsets,
+                };
             }
-            nb_steps += 1;
-            if next_element.ends_with('Z') {
-                break;
+            seen[state] = Some(step);
+            if is_z[node] {
+                z_offsets.push(step);
             }
+            node = self.map.next(node, instruction);
+        }
+        unreachable!("the instructions are cycled forever")
+    }
+}
+
+/// Path of a ghost, which ends up looping over the same (node, instruction index) states.
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    /// Number of steps before entering the loop
+    prefix: usize,
+    /// Number of steps of the loop
+    length: usize,
+    /// Steps before `prefix + length` at which the ghost is on a node ending with Z. The ones from `prefix` on
+    /// repeat every `length` steps.
+    z_offsets: Vec<usize>,
+}
+impl GhostCycle {
+    fn is_z_step(&self, step: usize) -> bool {
+        let step = if step < self.prefix {
+            step
+        } else {
+            self.prefix + (step - self.prefix) % self.length
+        };
+        self.z_offsets.contains(&step)
+    }
+
+    /// First step at which all the ghosts are on a node ending with Z, if they ever are
+    fn first_common_z_step(ghosts: &[GhostCycle]) -> Option<usize> {
+        // Steps before a ghost enters its loop are few, try them one by one
+        let in_prefix = ghosts
+            .iter()
+            .flat_map(|ghost| ghost.z_offsets.iter().filter(|&&step| step < ghost.prefix))
+            .filter(|&&step| ghosts.iter().all(|ghost| ghost.is_z_step(step)))
+            .min()
+            .copied();
+
+        // Once every ghost is in its loop, each one is on Z at a few residues modulo its loop length. Combine them
+        // with the Chinese remainder theorem.
+        let mut residues = vec![0i128];
+        let mut modulus = 1i128;
+        for ghost in ghosts {
+            let length = ghost.length as i128;
+            let ghost_residues: Vec<i128> = ghost
+                .z_offsets
+                .iter()
+                .filter(|&&step| step >= ghost.prefix)
+                .map(|&step| step as i128 % length)
+                .collect();
+            residues = residues
+                .iter()
+                .flat_map(|&r| {
+                    ghost_residues
+                        .iter()
+                        .filter_map(move |&c| crt((r, modulus), (c, length)))
+                })
+                .collect();
+            residues.sort_unstable();
+            residues.dedup();
+            modulus = modulus.lcm(&length);
+        }
+        let start = ghosts.iter().map(|ghost| ghost.prefix).max().unwrap_or(0) as i128;
+        let in_loop = residues
+            .into_iter()
+            .map(|r| r + Integer::div_ceil(&(start - r).max(0), &modulus) * modulus)
+            .min()
+            .and_then(|step| usize::try_from(step).ok());
+
+        match (in_prefix, in_loop) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
         }
-        nb_steps
     }
 }
 
+/// Solve `x = r1 mod m1` and `x = r2 mod m2`, returning `x mod lcm(m1, m2)` if there is a solution
+fn crt((r1, m1): (i128, i128), (r2, m2): (i128, i128)) -> Option<i128> {
+    let ExtendedGcd { gcd, x, .. } = m1.extended_gcd(&m2);
+    if (r2 - r1) % gcd != 0 {
+        return None;
+    }
+    let lcm = m1 / gcd * m2;
+    // m1 * x = gcd mod m2, so r1 + m1 * x * (r2 - r1) / gcd solves both
+    let k = (r2 - r1) / gcd * x % (m2 / gcd);
+    Some((r1 + m1 * k).rem_euclid(lcm))
+}
+
 pub fn run(file: &str) -> String {
     let data = std::fs::read_to_string(file).unwrap();
-    Puzzle::from_str(&data).unwrap().run().to_string()
+    match Puzzle::from_str(&data).unwrap().run() {
+        Some(nb_steps) => nb_steps.to_string(),
+        None => "the ghosts are never all on Z nodes".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let puzzle = Puzzle::from_str(
+            "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)",
+        )
+        .unwrap();
+        assert_eq!(puzzle.run(), Some(6));
+    }
+
+    #[test]
+    fn test_offset_cycles() {
+        // 1A loops over 1B and 1Z from step 1, 2A loops over 2Z, 2B and 2C from step 1: the LCM of the first Z
+        // steps (2 and 1) is not a solution.
+        let puzzle = Puzzle::from_str(
+            "L
+
+1A = (1B, 1B)
+1B = (1Z, 1Z)
+1Z = (1B, 1B)
+2A = (2Z, 2Z)
+2Z = (2B, 2B)
+2B = (2C, 2C)
+2C = (2Z, 2Z)",
+        )
+        .unwrap();
+        assert_eq!(
+            puzzle.cycle(puzzle.map.index("1A").unwrap()),
+            GhostCycle {
+                prefix: 1,
+                length: 2,
+                z_offsets: vec![2]
+            }
+        );
+        assert_eq!(
+            puzzle.cycle(puzzle.map.index("2A").unwrap()),
+            GhostCycle {
+                prefix: 1,
+                length: 3,
+                z_offsets: vec![1]
+            }
+        );
+        assert_eq!(puzzle.run(), Some(4));
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let puzzle = Puzzle::from_str(
+            "L
+
+1A = (1Z, 1Z)
+1Z = (1B, 1B)
+1B = (1Z, 1Z)
+2A = (2B, 2B)
+2B = (2Z, 2Z)
+2Z = (2B, 2B)",
+        )
+        .unwrap();
+        assert_eq!(puzzle.run(), None);
+    }
 }
@@ -1,71 +1,170 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::str::FromStr;
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 
 pub struct Report(Vec<History>);
 impl FromStr for Report {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let v: Result<Vec<History>, Error> = s.lines().map(History::from_str).collect();
+        let v: Result<Vec<History>, Error> = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| History::from_str(line).map_err(|e| anyhow!("line {}: {e}", i + 1)))
+            .collect();
         Ok(Self(v?))
     }
 }
 impl Report {
-    pub fn run_extrapolate_first(&self) -> isize {
-        self.0
-            .iter()
-            .map(|h| {
-                let (e, _) = h.run();
-                e
-            })
-            .sum()
+    pub fn run_extrapolate_first(&self) -> Result<i128, Error> {
+        self.sum(|h| h.previous(1))
     }
-    pub fn run_extrapolate_last(&self) -> isize {
-        self.0
-            .iter()
-            .map(|h| {
-                let (_, e) = h.run();
-                e
-            })
-            .sum()
+    pub fn run_extrapolate_last(&self) -> Result<i128, Error> {
+        self.forecast(1)
+    }
+    /// Sum of the `k`-th values after the end of every history
+    pub fn forecast(&self, k: usize) -> Result<i128, Error> {
+        self.sum(|h| h.next(k))
+    }
+
+    pub fn histories(&self) -> &[History] {
+        &self.0
+    }
+
+    fn sum(&self, f: impl Fn(&History) -> Result<i128, Error>) -> Result<i128, Error> {
+        self.0.iter().try_fold(0i128, |sum, h| {
+            sum.checked_add(f(h)?)
+                .ok_or(anyhow!("overflow summing the extrapolated values"))
+        })
     }
 }
 
-struct History(Vec<isize>);
+/// Sequence of values produced by a polynomial, fitted exactly with Newton's forward differences:
+/// `f(n) = sum of Δ^k f(0) * C(n, k)`. Histories too short to reach a row of zeros use every row available.
+#[derive(Debug)]
+pub struct History {
+    len: usize,
+    /// Forward differences of every order at the first value, up to the degree of the polynomial
+    coefficients: Vec<i128>,
+}
 impl FromStr for History {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let v: Result<Vec<isize>, ParseIntError> =
-            s.split_whitespace().map(|l| l.parse()).collect();
-        Ok(Self(v?))
+        let values = s
+            .split_whitespace()
+            .map(|l| l.parse())
+            .collect::<Result<Vec<i128>, _>>()?;
+
+        let mut coefficients = vec![];
+        let mut row = values.clone();
+        while row.iter().any(|&n| n != 0) {
+            coefficients.push(row[0]);
+            if row.len() == 1 {
+                break;
+            }
+            row = row
+                .windows(2)
+                .map(|w| w[1].checked_sub(w[0]))
+                .collect::<Option<_>>()
+                .ok_or(anyhow!("overflow computing the differences"))?;
+        }
+        if values.is_empty() {
+            return Err(anyhow!("empty history"));
+        }
+        Ok(Self {
+            len: values.len(),
+            coefficients,
+        })
     }
 }
 
 impl History {
-    fn run(&self) -> (isize, isize) {
-        let mut firsts = vec![];
-        let mut lasts = vec![];
-        let mut vin = self.0.clone();
-        loop {
-            let mut vout = vec![0isize; vin.len() - 1];
-
-            for i in 0..vout.len() {
-                vout[i] = vin[i + 1] - vin[i];
-            }
-            firsts.push(vin.first().copied().unwrap());
-            lasts.push(vin.last().copied().unwrap());
-            if vout.iter().all(|&n| n == 0) {
-                let mut first_extrapolated = vec![0isize; firsts.len() + 1];
-                (0..firsts.len()).rev().for_each(|i| {
-                    first_extrapolated[i] = firsts[i] - first_extrapolated[i + 1];
-                });
-                return (
-                    first_extrapolated.first().copied().unwrap(),
-                    lasts.into_iter().sum(),
-                );
-            }
+    /// Degree of the polynomial, 0 for a constant (or null) sequence
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
 
-            vin = vout;
+    /// Value of the sequence at `index`, 0 being the first value of the history. Negative indices are before it.
+    pub fn value_at(&self, index: i128) -> Result<i128, Error> {
+        let overflow = || anyhow!("overflow evaluating the sequence at {index}");
+        let mut value = 0i128;
+        // C(index, k), computed from C(index, k - 1) * (index - k + 1) / k. The division is exact, so once
+        // C(index, k - 1) and k are reduced by their gcd, k divides the factor and the product is the result.
+        let mut binomial = 1i128;
+        for (k, &coefficient) in self.coefficients.iter().enumerate() {
+            if k > 0 {
+                let k = k as i128;
+                let g = gcd(binomial.unsigned_abs(), k.unsigned_abs()) as i128;
+                binomial = index
+                    .checked_sub(k - 1)
+                    .and_then(|factor| (binomial / g).checked_mul(factor / (k / g)))
+                    .ok_or_else(overflow)?;
+            }
+            value = coefficient
+                .checked_mul(binomial)
+                .and_then(|term| value.checked_add(term))
+                .ok_or_else(overflow)?;
         }
+        Ok(value)
+    }
+
+    /// `k`-th value after the last one of the history
+    pub fn next(&self, k: usize) -> Result<i128, Error> {
+        self.value_at(self.len as i128 - 1 + k as i128)
+    }
+
+    /// `k`-th value before the first one of the history
+    pub fn previous(&self, k: usize) -> Result<i128, Error> {
+        self.value_at(-(k as i128))
+    }
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "0 3 6 9 12 15
+1 3 6 10 15 21
+10 13 16 21 30 45";
+
+    #[test]
+    fn test_example() {
+        let report = Report::from_str(EXAMPLE).unwrap();
+        assert_eq!(report.run_extrapolate_last().unwrap(), 114);
+        assert_eq!(report.run_extrapolate_first().unwrap(), 2);
+        let degrees: Vec<usize> = report.histories().iter().map(History::degree).collect();
+        assert_eq!(degrees, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_value_at() {
+        let history = History::from_str("1 3 6 10 15 21").unwrap();
+        assert_eq!(history.next(2).unwrap(), 36);
+        assert_eq!(history.previous(3).unwrap(), 1);
+        // Triangular numbers: f(n) = (n + 1)(n + 2) / 2
+        let n = 1_000_000_000_000i128;
+        assert_eq!(history.value_at(n).unwrap(), (n + 1) * (n + 2) / 2);
+        assert!(history.value_at(i128::MAX / 2).is_err());
+        // n * (n - 1) overflows, but not C(n, 2)
+        let n = 15_000_000_000_000_000_001i128;
+        assert_eq!(history.value_at(n).unwrap(), (n + 1) / 2 * (n + 2));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(History::from_str("").is_err());
+        // Too short to reach a row of zeros: extrapolated from the rows available
+        let history = History::from_str("1 2 4").unwrap();
+        assert_eq!((history.degree(), history.next(1).unwrap()), (2, 7));
+        let history = History::from_str("5").unwrap();
+        assert_eq!(history.next(1).unwrap(), 5);
+        assert_eq!(history.previous(1).unwrap(), 5);
+        assert_eq!(History::from_str("0 0").unwrap().next(5).unwrap(), 0);
     }
 }
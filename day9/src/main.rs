@@ -1,11 +1,35 @@
-use util::Cli;
+use std::str::FromStr;
+
+use common::Report;
+use util::clap::{self, Args};
 
 mod common;
 mod part1;
 mod part2;
 
+#[derive(Args)]
+struct Options {
+    /// Print the degree and the k-th next value of every history, and their sum, as an alternative to solving
+    #[arg(long)]
+    forecast: Option<usize>,
+}
+
 fn main() {
-    let cli = Cli::get();
+    let cli = util::Cli::<Options>::with_extras();
+    if let Some(k) = cli.extras.forecast {
+        let data = std::fs::read_to_string(&cli.file).unwrap();
+        let report = Report::from_str(&data).unwrap();
+        for (i, history) in report.histories().iter().enumerate() {
+            println!(
+                "history={} degree={} value={}",
+                i + 1,
+                history.degree(),
+                history.next(k).unwrap()
+            );
+        }
+        println!("sum={}", report.forecast(k).unwrap());
+        return;
+    }
     println!("part1={}", part1::run(&cli.file));
     println!("part2={}", part2::run(&cli.file));
 }
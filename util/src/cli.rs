@@ -1,12 +1,25 @@
-use clap::Parser;
+use clap::{Args, Parser};
 
 #[derive(Parser)]
-pub struct Cli {
+pub struct Cli<E: Args = NoExtras> {
     pub file: String,
+    /// Options of the day, given after the input file
+    #[command(flatten)]
+    pub extras: E,
 }
 
+/// No option besides the input file
+#[derive(Args)]
+pub struct NoExtras {}
+
 impl Cli {
     pub fn get() -> Self {
         Self::parse()
     }
 }
+
+impl<E: Args> Cli<E> {
+    pub fn with_extras() -> Self {
+        Self::parse()
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Error};
+
+pub type Point = (i128, i128);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    /// Counterclockwise with the y axis pointing up, clockwise on screen where it points down
+    Counterclockwise,
+    Clockwise,
+    /// The polygon has no area
+    Degenerate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Location {
+    Inside,
+    Boundary,
+    Outside,
+}
+
+fn checked(value: Option<i128>) -> Result<i128, Error> {
+    value.ok_or(anyhow!("integer overflow in polygon computation"))
+}
+
+/// z component of the cross product of `o->a` and `o->b`
+fn cross(o: Point, a: Point, b: Point) -> Result<i128, Error> {
+    let (ax, ay) = (
+        checked(a.0.checked_sub(o.0))?,
+        checked(a.1.checked_sub(o.1))?,
+    );
+    let (bx, by) = (
+        checked(b.0.checked_sub(o.0))?,
+        checked(b.1.checked_sub(o.1))?,
+    );
+    checked(
+        ax.checked_mul(by)
+            .zip(ay.checked_mul(bx))
+            .and_then(|(l, r)| l.checked_sub(r)),
+    )
+}
+
+/// Greatest common divisor of `|a|` and `|b|`, which overflows only for `gcd(i128::MIN, 0)`
+fn gcd(a: i128, b: i128) -> Result<i128, Error> {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    checked(i128::try_from(a).ok())
+}
+
+/// Polygon with integer vertices, given in order. The last vertex is joined to the first one.
+#[derive(Debug, Clone)]
+pub struct Polygon {
+    vertices: Vec<Point>,
+}
+impl Polygon {
+    pub fn new<T: TryInto<i128> + Copy + std::fmt::Debug>(
+        vertices: impl IntoIterator<Item = (T, T)>,
+    ) -> Result<Self, Error> {
+        let vertices = vertices
+            .into_iter()
+            .map(|(x, y)| {
+                let convert = |v: T| {
+                    v.try_into()
+                        .map_err(|_| anyhow!("coordinate {v:?} is out of range"))
+                };
+                Ok((convert(x)?, convert(y)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        if vertices.len() < 3 {
+            return Err(anyhow!(
+                "a polygon needs at least 3 vertices, got {}",
+                vertices.len()
+            ));
+        }
+        Ok(Self { vertices })
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Point, Point)> + '_ {
+        self.vertices
+            .iter()
+            .zip(self.vertices.iter().cycle().skip(1))
+            .map(|(&a, &b)| (a, b))
+    }
+
+    /// Twice the signed area (shoelace formula), positive for counterclockwise polygons. Twice the area of a
+    /// lattice polygon is always an integer.
+    pub fn double_signed_area(&self) -> Result<i128, Error> {
+        self.edges().try_fold(0i128, |area, (a, b)| {
+            checked(area.checked_add(cross((0, 0), a, b)?))
+        })
+    }
+
+    pub fn orientation(&self) -> Result<Orientation, Error> {
+        Ok(match self.double_signed_area()?.signum() {
+            1 => Orientation::Counterclockwise,
+            -1 => Orientation::Clockwise,
+            _ => Orientation::Degenerate,
+        })
+    }
+
+    /// Number of lattice points on the boundary, the sum of `gcd(|dx|, |dy|)` over the edges
+    pub fn boundary_points(&self) -> Result<i128, Error> {
+        self.edges().try_fold(0i128, |len, (a, b)| {
+            let step = gcd(
+                checked(b.0.checked_sub(a.0))?,
+                checked(b.1.checked_sub(a.1))?,
+            )?;
+            checked(len.checked_add(step))
+        })
+    }
+
+    /// Number of lattice points strictly inside the polygon, from Pick's theorem `A = i + b/2 - 1`
+    pub fn interior_points(&self) -> Result<i128, Error> {
+        let double_area = checked(self.double_signed_area()?.checked_abs())?;
+        let doubled = checked(
+            double_area
+                .checked_sub(self.boundary_points()?)
+                .and_then(|v| v.checked_add(2)),
+        )?;
+        Ok(doubled / 2)
+    }
+
+    /// Where `point` is relative to the polygon, by counting the edges crossed by a ray going toward +x
+    pub fn locate(&self, point: Point) -> Result<Location, Error> {
+        let mut inside = false;
+        for (a, b) in self.edges() {
+            let within = |v: i128, p: i128, q: i128| p.min(q) <= v && v <= p.max(q);
+            if cross(a, b, point)? == 0 && within(point.0, a.0, b.0) && within(point.1, a.1, b.1) {
+                return Ok(Location::Boundary);
+            }
+            // Half open on y so that a vertex on the ray is counted once
+            if (a.1 > point.1) != (b.1 > point.1) {
+                let side = cross(a, b, point)?;
+                if (side > 0) == (b.1 > a.1) {
+                    inside = !inside;
+                }
+            }
+        }
+        Ok(if inside {
+            Location::Inside
+        } else {
+            Location::Outside
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let square = Polygon::new([(0, 0), (4, 0), (4, 4), (0, 4)]).unwrap();
+        assert_eq!(square.double_signed_area().unwrap(), 32);
+        assert_eq!(square.orientation().unwrap(), Orientation::Counterclockwise);
+        assert_eq!(square.boundary_points().unwrap(), 16);
+        assert_eq!(square.interior_points().unwrap(), 9);
+        assert_eq!(square.locate((1, 2)).unwrap(), Location::Inside);
+        assert_eq!(square.locate((4, 2)).unwrap(), Location::Boundary);
+        assert_eq!(square.locate((0, 0)).unwrap(), Location::Boundary);
+        assert_eq!(square.locate((5, 0)).unwrap(), Location::Outside);
+        assert_eq!(square.locate((-1, 4)).unwrap(), Location::Outside);
+
+        let reversed = Polygon::new([(0, 4), (4, 4), (4, 0), (0, 0)]).unwrap();
+        assert_eq!(reversed.double_signed_area().unwrap(), -32);
+        assert_eq!(reversed.orientation().unwrap(), Orientation::Clockwise);
+        assert_eq!(reversed.interior_points().unwrap(), 9);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let triangle = Polygon::new([(0, 0), (4, 0), (0, 4)]).unwrap();
+        assert_eq!(triangle.double_signed_area().unwrap(), 16);
+        assert_eq!(triangle.boundary_points().unwrap(), 12);
+        assert_eq!(triangle.interior_points().unwrap(), 3);
+        assert_eq!(triangle.locate((2, 2)).unwrap(), Location::Boundary);
+        assert_eq!(triangle.locate((3, 3)).unwrap(), Location::Outside);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let huge = Polygon::new([(0, 0), (i128::MAX, 0), (0, i128::MAX)]).unwrap();
+        assert!(huge.double_signed_area().is_err());
+        let huge = Polygon::new([(0, 0), (i128::MIN, 0), (0, 1)]).unwrap();
+        assert!(huge.boundary_points().is_err());
+        assert!(huge.interior_points().is_err());
+        assert!(Polygon::new([(u128::MAX, 0), (0, 0), (0, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_too_few_vertices() {
+        let err = Polygon::new([(0, 0), (1, 0)]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a polygon needs at least 3 vertices, got 2"
+        );
+        assert!(Polygon::new::<i32>([]).is_err());
+    }
+}
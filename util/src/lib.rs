@@ -1,9 +1,11 @@
 mod cli;
 
+pub mod geometry;
 pub mod grid;
 mod puzzle_input;
 
-pub use cli::Cli;
+pub use clap;
+pub use cli::{Cli, NoExtras};
 pub use puzzle_input::fetch_puzzle_input;
 
 #[macro_export]
@@ -14,4 +16,9 @@ macro_rules! run {
         println!("part1={}", part1(&cli.file));
         println!("part2={}", part2(&cli.file));
     };
+    ($extras:ty) => {
+        let cli = util::Cli::<$extras>::with_extras();
+        println!("part1={}", part1(&cli.file, &cli.extras));
+        println!("part2={}", part2(&cli.file, &cli.extras));
+    };
 }